@@ -0,0 +1,567 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
+
+const LIMB_BITS: usize = 32;
+// Largest power of ten that fits in a limb, used for decimal conversion
+const DEC_CHUNK: u32 = 1_000_000_000;
+const DEC_CHUNK_DIGITS: usize = 9;
+
+// Little-endian base 2^32 limbs, never with trailing (most significant) zeros
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: BigUint,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint { limbs: vec![1] }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn normalized(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * LIMB_BITS - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        (self.limbs[i / LIMB_BITS] >> (i % LIMB_BITS)) & 1 == 1
+    }
+
+    fn set_bit(&mut self, i: usize) {
+        let limb = i / LIMB_BITS;
+        if self.limbs.len() <= limb {
+            self.limbs.resize(limb + 1, 0);
+        }
+        self.limbs[limb] |= 1 << (i % LIMB_BITS);
+    }
+
+    // self = self * 2 + bit
+    fn shl1_or(&mut self, bit: bool) {
+        let mut carry = bit as u32;
+        for l in self.limbs.iter_mut() {
+            let next = *l >> (LIMB_BITS - 1);
+            *l = (*l << 1) | carry;
+            carry = next;
+        }
+        if carry > 0 {
+            self.limbs.push(carry);
+        }
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << LIMB_BITS),
+            _ => None,
+        }
+    }
+
+    pub fn pow(&self, mut exp: u32) -> BigUint {
+        let mut base = self.clone();
+        let mut res = BigUint::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                res = &res * &base;
+            }
+            base = &base * &base;
+            exp >>= 1;
+        }
+        res
+    }
+
+    pub fn checked_sub(&self, rhs: &BigUint) -> Option<BigUint> {
+        if *self < *rhs {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let b = *rhs.limbs.get(i).unwrap_or(&0) as i64;
+            let mut d = a as i64 - b - borrow;
+            borrow = (d < 0) as i64;
+            if d < 0 {
+                d += 1 << LIMB_BITS;
+            }
+            limbs.push(d as u32);
+        }
+        Some(BigUint { limbs }.normalized())
+    }
+
+    fn mul_add_small(&self, m: u32, a: u32) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = a as u64;
+        for &l in &self.limbs {
+            let v = l as u64 * m as u64 + carry;
+            limbs.push(v as u32);
+            carry = v >> LIMB_BITS;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }.normalized()
+    }
+
+    fn div_rem_small(&self, d: u32) -> (BigUint, u32) {
+        assert!(d != 0, "division by zero");
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut rem = 0u64;
+        for (i, &l) in self.limbs.iter().enumerate().rev() {
+            let cur = (rem << LIMB_BITS) | l as u64;
+            limbs[i] = (cur / d as u64) as u32;
+            rem = cur % d as u64;
+        }
+        (BigUint { limbs }.normalized(), rem as u32)
+    }
+
+    pub fn div_rem(&self, rhs: &BigUint) -> (BigUint, BigUint) {
+        assert!(!rhs.is_zero(), "division by zero");
+        if *self < *rhs {
+            return (BigUint::zero(), self.clone());
+        }
+        if rhs.limbs.len() == 1 {
+            let (q, r) = self.div_rem_small(rhs.limbs[0]);
+            return (q, BigUint::from(r as u64));
+        }
+
+        // Binary long division, fine for the sizes seen in puzzles
+        let mut q = BigUint::zero();
+        let mut r = BigUint::zero();
+        for i in (0..self.bits()).rev() {
+            r.shl1_or(self.bit(i));
+            if r >= *rhs {
+                r = r.checked_sub(rhs).unwrap();
+                q.set_bit(i);
+            }
+        }
+        (q.normalized(), r)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(v: u64) -> Self {
+        BigUint {
+            limbs: vec![v as u32, (v >> LIMB_BITS) as u32],
+        }
+        .normalized()
+    }
+}
+
+impl From<usize> for BigUint {
+    fn from(v: usize) -> Self {
+        BigUint::from(v as u64)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= rhs.limbs.len() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0u64;
+        for (i, &a) in long.limbs.iter().enumerate() {
+            let v = a as u64 + *short.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(v as u32);
+            carry = v >> LIMB_BITS;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        self.checked_sub(rhs)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        if self.is_zero() || rhs.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let v = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = v as u32;
+                carry = v >> LIMB_BITS;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.normalized()
+    }
+}
+
+impl Div<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn div(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).0
+    }
+}
+
+impl Rem<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn rem(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).1
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('+').unwrap_or(s);
+        if s.is_empty() {
+            return Err("empty number".to_string());
+        }
+        s.chars()
+            .try_fold(BigUint::zero(), |acc, c| match c.to_digit(10) {
+                Some(d) => Ok(acc.mul_add_small(10, d)),
+                None => Err(format!("invalid digit '{}' in \"{}\"", c, s)),
+            })
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_decimal())
+    }
+}
+
+impl BigUint {
+    fn to_decimal(&self) -> String {
+        let mut chunks: Vec<u32> = vec![];
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (q, r) = n.div_rem_small(DEC_CHUNK);
+            chunks.push(r);
+            n = q;
+        }
+        match chunks.pop() {
+            None => "0".to_string(),
+            Some(top) => chunks.iter().rev().fold(top.to_string(), |acc, c| {
+                format!("{}{:0width$}", acc, c, width = DEC_CHUNK_DIGITS)
+            }),
+        }
+    }
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    pub fn one() -> BigInt {
+        BigInt::from(BigUint::one())
+    }
+
+    fn new(negative: bool, magnitude: BigUint) -> BigInt {
+        BigInt {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> BigUint {
+        self.magnitude.clone()
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        let m = self.magnitude.to_u64()?;
+        if self.negative {
+            0i64.checked_sub_unsigned(m)
+        } else {
+            i64::try_from(m).ok()
+        }
+    }
+
+    pub fn pow(&self, exp: u32) -> BigInt {
+        BigInt::new(self.negative && exp % 2 == 1, self.magnitude.pow(exp))
+    }
+
+    // Truncating division, matching the behaviour of primitive integers
+    pub fn div_rem(&self, rhs: &BigInt) -> (BigInt, BigInt) {
+        let (q, r) = self.magnitude.div_rem(&rhs.magnitude);
+        (
+            BigInt::new(self.negative != rhs.negative, q),
+            BigInt::new(self.negative, r),
+        )
+    }
+}
+
+impl From<BigUint> for BigInt {
+    fn from(magnitude: BigUint) -> Self {
+        BigInt::new(false, magnitude)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(v: i64) -> Self {
+        BigInt::new(v < 0, BigUint::from(v.unsigned_abs()))
+    }
+}
+
+impl From<isize> for BigInt {
+    fn from(v: isize) -> Self {
+        BigInt::from(v as i64)
+    }
+}
+
+impl From<usize> for BigInt {
+    fn from(v: usize) -> Self {
+        BigInt::from(BigUint::from(v))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude)
+    }
+}
+
+impl Add<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::new(self.negative, &self.magnitude + &rhs.magnitude);
+        }
+        match self.magnitude.cmp(&rhs.magnitude) {
+            Ordering::Less => BigInt::new(rhs.negative, &rhs.magnitude - &self.magnitude),
+            _ => BigInt::new(self.negative, &self.magnitude - &rhs.magnitude),
+        }
+    }
+}
+
+impl Sub<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl Mul<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != rhs.negative,
+            &self.magnitude * &rhs.magnitude,
+        )
+    }
+}
+
+impl Div<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).0
+    }
+}
+
+impl Rem<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn rem(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).1
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('-') {
+            Some(rest) => Ok(BigInt::new(true, rest.parse()?)),
+            None => Ok(BigInt::from(s.parse::<BigUint>()?)),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.negative, "", &self.magnitude.to_decimal())
+    }
+}
+
+// Owned operand variants delegate to the by-reference implementations
+macro_rules! forward_owned_binop {
+    ($t:ty, $tr:ident, $method:ident) => {
+        impl $tr<$t> for $t {
+            type Output = $t;
+
+            fn $method(self, rhs: $t) -> $t {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $tr<&$t> for $t {
+            type Output = $t;
+
+            fn $method(self, rhs: &$t) -> $t {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl $tr<$t> for &$t {
+            type Output = $t;
+
+            fn $method(self, rhs: $t) -> $t {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+forward_owned_binop!(BigUint, Add, add);
+forward_owned_binop!(BigUint, Sub, sub);
+forward_owned_binop!(BigUint, Mul, mul);
+forward_owned_binop!(BigUint, Div, div);
+forward_owned_binop!(BigUint, Rem, rem);
+forward_owned_binop!(BigInt, Add, add);
+forward_owned_binop!(BigInt, Sub, sub);
+forward_owned_binop!(BigInt, Mul, mul);
+forward_owned_binop!(BigInt, Div, div);
+forward_owned_binop!(BigInt, Rem, rem);
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::bigint::{BigInt, BigUint};
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_uint() {
+        let fact = (1..=30usize).fold(BigUint::one(), |acc, i| acc * BigUint::from(i));
+        assert_eq!(fact.to_string(), "265252859812191058636308480000000");
+        assert_eq!(fact, big("265252859812191058636308480000000"));
+
+        let a = big("123456789012345678901234567890");
+        let b = big("987654321098765432");
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(q, big("124999998860"));
+        assert_eq!(r, big("925925953827160370"));
+        assert_eq!(&q * &b + &r, a);
+        assert_eq!(&a - &a, BigUint::zero());
+        assert!(b.checked_sub(&a).is_none());
+        assert_eq!(
+            BigUint::from(2usize).pow(100).to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(format!("{:>5}", BigUint::from(42usize)), "   42");
+        assert!("12a".parse::<BigUint>().is_err());
+    }
+
+    #[test]
+    fn test_int() {
+        let a: BigInt = "-123456789012345678901234567890".parse().unwrap();
+        let b = BigInt::from(7i64);
+        assert_eq!((&a / &b).to_string(), "-17636684144620811271604938270");
+        assert_eq!((&a % &b).to_string(), "0");
+        assert_eq!(
+            (BigInt::from(-7i64) % BigInt::from(3i64)).to_i64(),
+            Some(-1)
+        );
+        assert_eq!((BigInt::from(5i64) - BigInt::from(8i64)).to_i64(), Some(-3));
+        assert_eq!(
+            (BigInt::from(-5i64) * BigInt::from(-8i64)).to_i64(),
+            Some(40)
+        );
+        assert!(a < b);
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(format!("{}", -BigInt::zero()), "0");
+    }
+}
@@ -0,0 +1,199 @@
+use std::ops::{Add, Mul};
+
+// Exact n choose k, None if it does not fit in a u64
+pub fn binomial(n: u64, k: u64) -> Option<u64> {
+    if k > n {
+        return Some(0);
+    }
+    // Each partial product is itself a smaller binomial, so never above the result
+    let k = k.min(n - k);
+    let mut res: u128 = 1;
+    for i in 0..k {
+        res = res * (n - i) as u128 / (i + 1) as u128;
+        if res > u64::MAX as u128 {
+            return None;
+        }
+    }
+    Some(res as u64)
+}
+
+// All orderings of the items in lexicographic order of position
+pub struct Permutations<'a, T> {
+    items: &'a [T],
+    indices: Option<Vec<usize>>,
+}
+
+pub fn permutations<T: Clone>(items: &[T]) -> Permutations<'_, T> {
+    Permutations {
+        items,
+        indices: Some((0..items.len()).collect()),
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<'_, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let res = indices.iter().map(|i| self.items[*i].clone()).collect();
+
+        // Step to the next permutation: swap the last ascent with the smallest
+        // larger element after it, then reverse the tail
+        match (1..indices.len())
+            .rev()
+            .find(|i| indices[i - 1] < indices[*i])
+        {
+            Some(i) => {
+                let j = (i..indices.len())
+                    .rev()
+                    .find(|j| indices[*j] > indices[i - 1])
+                    .unwrap();
+                indices.swap(i - 1, j);
+                indices[i..].reverse();
+            }
+            None => self.indices = None,
+        }
+        Some(res)
+    }
+}
+
+// All k-element subsets, keeping the items' order
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    indices: Option<Vec<usize>>,
+}
+
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (k <= items.len()).then(|| (0..k).collect()),
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<'_, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let res = indices.iter().map(|i| self.items[*i].clone()).collect();
+
+        let (n, k) = (self.items.len(), indices.len());
+        match (0..k).rev().find(|i| indices[*i] < n - k + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..k {
+                    indices[j] = indices[j - 1] + 1;
+                }
+            }
+            None => self.indices = None,
+        }
+        Some(res)
+    }
+}
+
+// One pick from every set, the last set varying fastest
+pub struct CartesianProduct<'a, T> {
+    sets: &'a [Vec<T>],
+    indices: Option<Vec<usize>>,
+}
+
+pub fn cartesian_product<T: Clone>(sets: &[Vec<T>]) -> CartesianProduct<'_, T> {
+    CartesianProduct {
+        sets,
+        indices: sets
+            .iter()
+            .all(|s| !s.is_empty())
+            .then(|| vec![0; sets.len()]),
+    }
+}
+
+impl<T: Clone> Iterator for CartesianProduct<'_, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = self.indices.as_mut()?;
+        let res = indices
+            .iter()
+            .zip(self.sets)
+            .map(|(i, s)| s[*i].clone())
+            .collect();
+
+        match (0..indices.len())
+            .rev()
+            .find(|i| indices[*i] + 1 < self.sets[*i].len())
+        {
+            Some(i) => {
+                indices[i] += 1;
+                indices[i + 1..].iter_mut().for_each(|j| *j = 0);
+            }
+            None => self.indices = None,
+        }
+        Some(res)
+    }
+}
+
+// Distribution of the sum of two independent variables, each given as weights
+// indexed by value
+pub fn convolve<T>(a: &[T], b: &[T]) -> Vec<T>
+where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+{
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut res = vec![T::default(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            res[i + j] = res[i + j] + *x * *y;
+        }
+    }
+    res
+}
+
+// Number of ways `n` dice with faces 1..=faces can roll each total
+pub fn dice_sums(n: usize, faces: usize) -> Vec<usize> {
+    let mut die = vec![1; faces + 1];
+    die[0] = 0;
+    (0..n).fold(vec![1], |dist, _| convolve(&dist, &die))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::combinatorics::*;
+
+    #[test]
+    fn test_counting() {
+        assert_eq!(binomial(5, 2), Some(10));
+        assert_eq!(binomial(2, 5), Some(0));
+        assert_eq!(binomial(67, 33), Some(14226520737620288370));
+        assert_eq!(binomial(68, 34), None);
+        assert_eq!(binomial(u64::MAX, 1), Some(u64::MAX));
+
+        let perms: Vec<String> = permutations(&['a', 'b', 'c'])
+            .map(|p| p.into_iter().collect())
+            .collect();
+        assert_eq!(perms, ["abc", "acb", "bac", "bca", "cab", "cba"]);
+        assert_eq!(permutations(&[0; 7]).count(), 5040);
+        assert_eq!(permutations::<u8>(&[]).count(), 1);
+
+        let combs: Vec<Vec<u8>> = combinations(&[1, 2, 3, 4], 2).collect();
+        assert_eq!(combs.len(), 6);
+        assert_eq!(combs[0], [1, 2]);
+        assert_eq!(combs[5], [3, 4]);
+        assert_eq!(combinations(&[1, 2], 3).count(), 0);
+        assert_eq!(combinations(&[1, 2], 0).count(), 1);
+
+        let sets = vec![vec![0, 1], vec![5], vec![7, 8, 9]];
+        let product: Vec<Vec<u8>> = cartesian_product(&sets).collect();
+        assert_eq!(product.len(), 6);
+        assert_eq!(product[1], [0, 5, 8]);
+        assert_eq!(cartesian_product(&[vec![1], vec![]]).count(), 0);
+    }
+
+    #[test]
+    fn test_distributions() {
+        assert_eq!(dice_sums(3, 3), [0, 0, 0, 1, 3, 6, 7, 6, 3, 1]);
+        assert_eq!(dice_sums(2, 6).iter().sum::<usize>(), 36);
+        assert_eq!(convolve(&[0.5, 0.5], &[0.5, 0.5]), [0.25, 0.5, 0.25]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Index;
+
+// Multiset counting how often each item was seen. Items never go below zero
+// and vanish once their count drops to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<T: Eq + Hash> {
+    counts: HashMap<T, usize>,
+}
+
+// Which item wins when several share the highest (or lowest) count
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tie {
+    Smallest,
+    Largest,
+    Reject, // no answer unless there is a single winner
+}
+
+impl<T: Eq + Hash> Default for Counter<T> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> Counter<T> {
+    pub fn new() -> Counter<T> {
+        Counter::default()
+    }
+
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1);
+    }
+
+    pub fn add_n(&mut self, item: T, n: usize) {
+        if n > 0 {
+            *self.counts.entry(item).or_default() += n;
+        }
+    }
+
+    pub fn remove_n(&mut self, item: &T, n: usize) {
+        if let Some(c) = self.counts.get_mut(item) {
+            *c = c.saturating_sub(n);
+            if *c == 0 {
+                self.counts.remove(item);
+            }
+        }
+    }
+
+    pub fn get(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    // Number of distinct items
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(k, v)| (k, *v))
+    }
+
+    // Difference between the highest and lowest count, 0 when empty
+    pub fn spread(&self) -> usize {
+        let max = self.counts.values().max().unwrap_or(&0);
+        let min = self.counts.values().min().unwrap_or(&0);
+        max - min
+    }
+}
+
+impl<T: Eq + Hash + Clone> Counter<T> {
+    pub fn merge(&mut self, other: &Counter<T>) {
+        for (k, v) in other.iter() {
+            self.add_n(k.clone(), v);
+        }
+    }
+
+    pub fn subtract(&mut self, other: &Counter<T>) {
+        for (k, v) in other.iter() {
+            self.remove_n(k, v);
+        }
+    }
+}
+
+impl<T: Eq + Hash + Ord> Counter<T> {
+    fn pick(&self, best: usize, tie: Tie) -> Option<&T> {
+        let mut winners = self.iter().filter(|(_, c)| *c == best).map(|(k, _)| k);
+        match tie {
+            Tie::Smallest => winners.min(),
+            Tie::Largest => winners.max(),
+            Tie::Reject => match (winners.next(), winners.next()) {
+                (Some(k), None) => Some(k),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn most_common(&self, tie: Tie) -> Option<&T> {
+        self.pick(*self.counts.values().max()?, tie)
+    }
+
+    pub fn least_common(&self, tie: Tie) -> Option<&T> {
+        self.pick(*self.counts.values().min()?, tie)
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Eq + Hash> Extend<T> for Counter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T: Eq + Hash> Index<&T> for Counter<T> {
+    type Output = usize;
+
+    fn index(&self, item: &T) -> &usize {
+        self.counts.get(item).unwrap_or(&0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::counter::{Counter, Tie};
+
+    #[test]
+    fn test_counter() {
+        let mut c: Counter<char> = "abracadabra".chars().collect();
+        assert_eq!((c[&'a'], c[&'b'], c[&'z']), (5, 2, 0));
+        assert_eq!((c.len(), c.total(), c.spread()), (5, 11, 4));
+        assert_eq!(c.most_common(Tie::Reject), Some(&'a'));
+        assert_eq!(c.least_common(Tie::Smallest), Some(&'c'));
+        assert_eq!(c.least_common(Tie::Largest), Some(&'d'));
+        assert_eq!(c.least_common(Tie::Reject), None);
+
+        let other: Counter<char> = "bad".chars().collect();
+        c.subtract(&other);
+        assert_eq!((c[&'a'], c[&'b'], c.len()), (4, 1, 4));
+        c.merge(&other);
+        c.merge(&other);
+        assert_eq!((c[&'d'], c.total()), (2, 14));
+
+        let empty: Counter<char> = Counter::new();
+        assert_eq!((empty.most_common(Tie::Largest), empty.spread()), (None, 0));
+    }
+}
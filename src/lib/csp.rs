@@ -0,0 +1,184 @@
+// Small finite-domain constraint solver: backtracking search with propagation.
+// Domains are bitsets, so values must be below 64.
+
+type Domain = u64;
+
+pub type Check<'a> = Box<dyn Fn(&[Option<usize>]) -> bool + 'a>;
+
+enum Constraint<'a> {
+    AllDifferent(Vec<usize>),
+    // Called with the values of the scope, None where not yet decided. Must only
+    // return false when no completion of the partial assignment can satisfy it.
+    Custom(Vec<usize>, Check<'a>),
+}
+
+#[derive(Default)]
+pub struct Csp<'a> {
+    domains: Vec<Domain>,
+    constraints: Vec<Constraint<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solutions {
+    None,
+    Unique(Vec<usize>),
+    Many(Vec<Vec<usize>>), // the first few of them
+}
+
+fn single(d: Domain) -> Option<usize> {
+    match d.count_ones() {
+        1 => Some(d.trailing_zeros() as usize),
+        _ => None,
+    }
+}
+
+fn values(d: Domain) -> impl Iterator<Item = usize> {
+    (0..Domain::BITS as usize).filter(move |v| d & (1 << v) != 0)
+}
+
+impl<'a> Csp<'a> {
+    pub fn new() -> Csp<'a> {
+        Csp::default()
+    }
+
+    pub fn add_var<I: IntoIterator<Item = usize>>(&mut self, domain: I) -> usize {
+        let d = domain.into_iter().fold(0, |d, v| {
+            assert!(v < Domain::BITS as usize, "domain value {} too large", v);
+            d | 1 << v
+        });
+        self.domains.push(d);
+        self.domains.len() - 1
+    }
+
+    pub fn all_different(&mut self, vars: Vec<usize>) {
+        self.constraints.push(Constraint::AllDifferent(vars));
+    }
+
+    pub fn constrain<F>(&mut self, scope: Vec<usize>, check: F)
+    where
+        F: Fn(&[Option<usize>]) -> bool + 'a,
+    {
+        self.constraints
+            .push(Constraint::Custom(scope, Box::new(check)));
+    }
+
+    // Prunes domains to a fixpoint, false when some domain is wiped out
+    fn propagate(&self, domains: &mut [Domain]) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for c in &self.constraints {
+                match c {
+                    Constraint::AllDifferent(vars) => {
+                        for &v in vars {
+                            if let Some(x) = single(domains[v]) {
+                                for &w in vars.iter().filter(|w| **w != v) {
+                                    if domains[w] & 1 << x != 0 {
+                                        domains[w] &= !(1 << x);
+                                        changed = true;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Constraint::Custom(scope, check) => {
+                        let mut assigned: Vec<Option<usize>> =
+                            scope.iter().map(|v| single(domains[*v])).collect();
+                        if !check(&assigned) {
+                            return false;
+                        }
+                        for (i, &v) in scope.iter().enumerate() {
+                            if assigned[i].is_some() {
+                                continue;
+                            }
+                            for x in values(domains[v]) {
+                                assigned[i] = Some(x);
+                                if !check(&assigned) {
+                                    domains[v] &= !(1 << x);
+                                    changed = true;
+                                }
+                            }
+                            assigned[i] = None;
+                        }
+                    }
+                }
+            }
+            if domains.contains(&0) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn search(&self, mut domains: Vec<Domain>, limit: usize, found: &mut Vec<Vec<usize>>) {
+        if found.len() >= limit || !self.propagate(&mut domains) {
+            return;
+        }
+
+        let open = (0..domains.len())
+            .filter(|v| domains[*v].count_ones() > 1)
+            .min_by_key(|v| domains[*v].count_ones());
+
+        match open {
+            None => found.push(domains.iter().map(|d| single(*d).unwrap()).collect()),
+            Some(v) => {
+                for x in values(domains[v]) {
+                    let mut next = domains.clone();
+                    next[v] = 1 << x;
+                    self.search(next, limit, found);
+                }
+            }
+        }
+    }
+
+    // Up to `limit` assignments, indexed by variable
+    pub fn solutions(&self, limit: usize) -> Vec<Vec<usize>> {
+        let mut found = vec![];
+        self.search(self.domains.clone(), limit, &mut found);
+        found
+    }
+
+    pub fn solve(&self) -> Solutions {
+        let mut found = self.solutions(2);
+        match found.len() {
+            0 => Solutions::None,
+            1 => Solutions::Unique(found.pop().unwrap()),
+            _ => Solutions::Many(found),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::csp::{Csp, Solutions};
+
+    #[test]
+    fn test_solve() {
+        // a + b = 5 with a < b, both different from c = 3
+        let mut csp = Csp::new();
+        let a = csp.add_var(0..5);
+        let b = csp.add_var(0..5);
+        let c = csp.add_var([3]);
+        csp.all_different(vec![a, b, c]);
+        csp.constrain(vec![a, b], |v| match v {
+            [Some(a), Some(b)] => a + b == 5 && a < b,
+            _ => true,
+        });
+        assert_eq!(csp.solve(), Solutions::Unique(vec![1, 4, 3]));
+    }
+
+    #[test]
+    fn test_ambiguous_and_contradictory() {
+        let mut csp = Csp::new();
+        let vars: Vec<usize> = (0..3).map(|_| csp.add_var(0..3)).collect();
+        csp.all_different(vars.clone());
+        assert_eq!(csp.solutions(10).len(), 6);
+        assert!(matches!(csp.solve(), Solutions::Many(_)));
+
+        csp.add_var([0]);
+        csp.all_different(vec![vars[0], 3]);
+        csp.all_different(vec![vars[1], 3]);
+        csp.all_different(vec![vars[2], 3]);
+        assert_eq!(csp.solve(), Solutions::None);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Adjacency-list graph with nodes interned to dense ids. Undirected edges are
+// stored in both directions.
+#[derive(Debug, Clone)]
+pub struct Graph<N, E> {
+    directed: bool,
+    nodes: Vec<N>,
+    ids: HashMap<N, usize>,
+    adj: Vec<Vec<(usize, E)>>,
+}
+
+impl<N, E> Graph<N, E>
+where
+    N: Clone + Eq + Hash,
+    E: Clone,
+{
+    pub fn new(directed: bool) -> Graph<N, E> {
+        Graph {
+            directed,
+            nodes: vec![],
+            ids: HashMap::new(),
+            adj: vec![],
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // Id of the node, adding it if it is new
+    pub fn intern(&mut self, n: N) -> usize {
+        if let Some(&id) = self.ids.get(&n) {
+            return id;
+        }
+        self.nodes.push(n.clone());
+        self.adj.push(vec![]);
+        self.ids.insert(n, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    pub fn id(&self, n: &N) -> Option<usize> {
+        self.ids.get(n).copied()
+    }
+
+    pub fn node(&self, id: usize) -> &N {
+        &self.nodes[id]
+    }
+
+    pub fn add_edge(&mut self, a: N, b: N, e: E) -> (usize, usize) {
+        let (a, b) = (self.intern(a), self.intern(b));
+        if !self.directed {
+            self.adj[b].push((a, e.clone()));
+        }
+        self.adj[a].push((b, e));
+        (a, b)
+    }
+
+    pub fn neighbours(&self, id: usize) -> impl Iterator<Item = (usize, &E)> {
+        self.adj[id].iter().map(|(n, e)| (*n, e))
+    }
+
+    // Kahn's algorithm, fails when the graph has a cycle
+    pub fn topological_sort(&self) -> Result<Vec<usize>, String> {
+        if !self.directed {
+            return Err("topological sort needs a directed graph".to_string());
+        }
+
+        let mut in_degree = vec![0; self.len()];
+        for edges in &self.adj {
+            for (n, _) in edges {
+                in_degree[*n] += 1;
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..self.len())
+            .filter(|n| in_degree[*n] == 0)
+            .rev()
+            .collect();
+        let mut order = vec![];
+        while let Some(u) = ready.pop() {
+            order.push(u);
+            for (v, _) in &self.adj[u] {
+                in_degree[*v] -= 1;
+                if in_degree[*v] == 0 {
+                    ready.push(*v);
+                }
+            }
+        }
+
+        match order.len() == self.len() {
+            true => Ok(order),
+            false => Err("graph has a cycle".to_string()),
+        }
+    }
+
+    // Tarjan's algorithm, components come out in reverse topological order
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        struct Tarjan {
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<usize>,
+            next: usize,
+            components: Vec<Vec<usize>>,
+        }
+
+        fn visit<N, E>(g: &Graph<N, E>, t: &mut Tarjan, u: usize) {
+            t.index[u] = Some(t.next);
+            t.low[u] = t.next;
+            t.next += 1;
+            t.stack.push(u);
+            t.on_stack[u] = true;
+
+            for (v, _) in &g.adj[u] {
+                match t.index[*v] {
+                    None => {
+                        visit(g, t, *v);
+                        t.low[u] = t.low[u].min(t.low[*v]);
+                    }
+                    Some(i) if t.on_stack[*v] => t.low[u] = t.low[u].min(i),
+                    _ => {}
+                }
+            }
+
+            if Some(t.low[u]) == t.index[u] {
+                let mut component = vec![];
+                while let Some(v) = t.stack.pop() {
+                    t.on_stack[v] = false;
+                    component.push(v);
+                    if v == u {
+                        break;
+                    }
+                }
+                t.components.push(component);
+            }
+        }
+
+        let mut t = Tarjan {
+            index: vec![None; self.len()],
+            low: vec![0; self.len()],
+            on_stack: vec![false; self.len()],
+            stack: vec![],
+            next: 0,
+            components: vec![],
+        };
+        for u in 0..self.len() {
+            if t.index[u].is_none() {
+                visit(self, &mut t, u);
+            }
+        }
+        t.components
+    }
+
+    // Components ignoring edge direction (weakly connected for directed graphs)
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        let mut undirected: Vec<Vec<usize>> = vec![vec![]; self.len()];
+        for (u, edges) in self.adj.iter().enumerate() {
+            for (v, _) in edges {
+                undirected[u].push(*v);
+                undirected[*v].push(u);
+            }
+        }
+
+        let mut seen = vec![false; self.len()];
+        let mut components = vec![];
+        for root in 0..self.len() {
+            if seen[root] {
+                continue;
+            }
+            seen[root] = true;
+            let mut component = vec![];
+            let mut stack = vec![root];
+            while let Some(u) = stack.pop() {
+                component.push(u);
+                for v in &undirected[u] {
+                    if !seen[*v] {
+                        seen[*v] = true;
+                        stack.push(*v);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    // Every path from `from` to `to` that visits no node twice
+    pub fn simple_paths(&self, from: usize, to: usize) -> Vec<Vec<usize>> {
+        fn walk<N, E>(
+            g: &Graph<N, E>,
+            to: usize,
+            path: &mut Vec<usize>,
+            on_path: &mut Vec<bool>,
+            paths: &mut Vec<Vec<usize>>,
+        ) {
+            let u = *path.last().unwrap();
+            if u == to {
+                paths.push(path.clone());
+                return;
+            }
+            for (v, _) in &g.adj[u] {
+                if !on_path[*v] {
+                    on_path[*v] = true;
+                    path.push(*v);
+                    walk(g, to, path, on_path, paths);
+                    path.pop();
+                    on_path[*v] = false;
+                }
+            }
+        }
+
+        let mut on_path = vec![false; self.len()];
+        on_path[from] = true;
+        let mut paths = vec![];
+        walk(self, to, &mut vec![from], &mut on_path, &mut paths);
+        paths
+    }
+}
+
+impl Graph<String, ()> {
+    // Edge list with one `a-b` (undirected) or `a -> b` (directed) edge per line
+    pub fn parse_edges(input: &[String]) -> Result<Graph<String, ()>, String> {
+        let lines: Vec<&String> = input.iter().filter(|l| !l.trim().is_empty()).collect();
+        let directed = lines.iter().any(|l| l.contains("->"));
+        let sep = if directed { "->" } else { "-" };
+
+        let mut graph = Graph::new(directed);
+        for line in lines {
+            match line.split_once(sep) {
+                Some((a, b)) if !a.trim().is_empty() && !b.trim().is_empty() => {
+                    graph.add_edge(a.trim().to_string(), b.trim().to_string(), ());
+                }
+                _ => return Err(format!("invalid edge \"{}\"", line)),
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::graph::Graph;
+
+    fn parse(lines: &[&str]) -> Graph<String, ()> {
+        let input: Vec<String> = lines.iter().map(|s| s.to_string()).collect();
+        Graph::parse_edges(&input).unwrap()
+    }
+
+    fn names(g: &Graph<String, ()>, ids: &[usize]) -> Vec<String> {
+        ids.iter().map(|i| g.node(*i).clone()).collect()
+    }
+
+    #[test]
+    fn test_directed() {
+        let g = parse(&["a -> b", "b -> c", "a -> c", "d -> a"]);
+        assert!(g.is_directed());
+        assert_eq!(
+            names(&g, &g.topological_sort().unwrap()),
+            ["d", "a", "b", "c"]
+        );
+        assert_eq!(g.simple_paths(g.id(&"a".to_string()).unwrap(), 2).len(), 2);
+
+        let cyclic = parse(&["a -> b", "b -> c", "c -> a", "c -> d"]);
+        assert!(cyclic.topological_sort().is_err());
+        let mut scc: Vec<Vec<String>> = cyclic
+            .strongly_connected_components()
+            .iter()
+            .map(|c| {
+                let mut c = names(&cyclic, c);
+                c.sort();
+                c
+            })
+            .collect();
+        scc.sort();
+        assert_eq!(scc, [vec!["a", "b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_undirected() {
+        let g = parse(&["a-b", "b-c", "x-y"]);
+        assert!(!g.is_directed());
+        assert_eq!(g.len(), 5);
+        assert_eq!(g.connected_components(), [vec![0, 1, 2], vec![3, 4]]);
+        assert_eq!(g.neighbours(1).count(), 2);
+        assert_eq!(g.simple_paths(0, 2), [vec![0, 1, 2]]);
+        assert!(g.topological_sort().is_err());
+    }
+}
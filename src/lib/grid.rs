@@ -0,0 +1,247 @@
+// Transforms of row-major grids given as a shape and its cells, in the same
+// (Shape, Vec<T>) form as lib::pad
+use crate::lib::{Point, Shape};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    X, // mirroring reverses each row, folding brings the right over the left
+    Y, // mirroring reverses the rows, folding brings the bottom up
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Margins {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+impl Margins {
+    pub fn uniform(by: usize) -> Margins {
+        Margins {
+            left: by,
+            top: by,
+            right: by,
+            bottom: by,
+        }
+    }
+}
+
+// Builds a new grid where f gives the source cell for every target point
+fn remap<T, F>(s: &Shape, img: &[T], to: Shape, f: F) -> (Shape, Vec<T>)
+where
+    T: Clone,
+    F: Fn(usize, usize) -> (usize, usize),
+{
+    let cells = (0..to.h)
+        .flat_map(|y| (0..to.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (sx, sy) = f(x, y);
+            img[sy * s.w + sx].clone()
+        })
+        .collect();
+    (to, cells)
+}
+
+pub fn transpose<T: Clone>(s: &Shape, img: &[T]) -> (Shape, Vec<T>) {
+    remap(s, img, Shape::new(s.h, s.w), |x, y| (y, x))
+}
+
+pub fn mirror<T: Clone>(s: &Shape, img: &[T], axis: Axis) -> (Shape, Vec<T>) {
+    match axis {
+        Axis::X => remap(s, img, *s, |x, y| (s.w - 1 - x, y)),
+        Axis::Y => remap(s, img, *s, |x, y| (x, s.h - 1 - y)),
+    }
+}
+
+// Clockwise by a number of quarter turns
+pub fn rotate<T: Clone>(s: &Shape, img: &[T], quarter_turns: usize) -> (Shape, Vec<T>) {
+    let (w, h) = (s.w, s.h);
+    match quarter_turns % 4 {
+        0 => (*s, img.to_vec()),
+        1 => remap(s, img, Shape::new(h, w), |x, y| (y, h - 1 - x)),
+        2 => remap(s, img, *s, |x, y| (w - 1 - x, h - 1 - y)),
+        _ => remap(s, img, Shape::new(h, w), |x, y| (w - 1 - y, x)),
+    }
+}
+
+pub fn sub_grid<T: Clone>(
+    s: &Shape,
+    img: &[T],
+    origin: Point,
+    size: Shape,
+) -> Result<(Shape, Vec<T>), String> {
+    if origin.x + size.w > s.w || origin.y + size.h > s.h {
+        return Err(format!(
+            "{}x{} at {},{} is outside the {}x{} grid",
+            size.w, size.h, origin.x, origin.y, s.w, s.h
+        ));
+    }
+    Ok(remap(s, img, size, |x, y| (origin.x + x, origin.y + y)))
+}
+
+// Cuts the margins off each side
+pub fn crop<T: Clone>(s: &Shape, img: &[T], by: Margins) -> Result<(Shape, Vec<T>), String> {
+    let size = Shape::new(
+        s.w.checked_sub(by.left + by.right)
+            .ok_or("crop wider than the grid")?,
+        s.h.checked_sub(by.top + by.bottom)
+            .ok_or("crop taller than the grid")?,
+    );
+    sub_grid(s, img, Point::new(by.left, by.top), size)
+}
+
+pub fn pad<T: Clone>(s: &Shape, img: &[T], by: Margins, padder: T) -> (Shape, Vec<T>) {
+    let to = Shape::new(s.w + by.left + by.right, s.h + by.top + by.bottom);
+    let mut cells = vec![padder; to.len()];
+    for (y, row) in img.chunks(s.w.max(1)).enumerate() {
+        let start = (y + by.top) * to.w + by.left;
+        cells[start..start + row.len()].clone_from_slice(row);
+    }
+    (to, cells)
+}
+
+// Repeats the grid tiles.w times across and tiles.h times down, with each copy's
+// values passed through f along with the tile's position
+pub fn tile<T, F>(s: &Shape, img: &[T], tiles: Shape, f: F) -> (Shape, Vec<T>)
+where
+    F: Fn(&T, Point) -> T,
+{
+    let to = Shape::new(s.w * tiles.w, s.h * tiles.h);
+    if to.len() == 0 {
+        return (to, vec![]);
+    }
+    let cells = (0..to.h)
+        .flat_map(|y| (0..to.w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let t = Point::new(x / s.w, y / s.h);
+            f(&img[(y % s.h) * s.w + x % s.w], t)
+        })
+        .collect();
+    (to, cells)
+}
+
+// Folds along the line at `at` on the axis, which disappears. Cells the same
+// distance from the line are merged. When the folded part is the larger one
+// the grid flips over, so the result always starts at the origin. The line has
+// to lie on the grid, otherwise some cells would have nothing folded onto them.
+pub fn fold<T, F>(
+    s: &Shape,
+    img: &[T],
+    axis: Axis,
+    at: usize,
+    merge: F,
+) -> Result<(Shape, Vec<T>), String>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> T,
+{
+    let len = match axis {
+        Axis::X => s.w,
+        Axis::Y => s.h,
+    };
+    if at >= len {
+        return Err(format!(
+            "fold line {} is outside the {}x{} grid",
+            at, s.w, s.h
+        ));
+    }
+    let folded = at.max(len.saturating_sub(at + 1));
+    let to = match axis {
+        Axis::X => Shape::new(folded, s.h),
+        Axis::Y => Shape::new(s.w, folded),
+    };
+
+    let mut cells: Vec<Option<T>> = vec![None; to.len()];
+    for (i, v) in img.iter().enumerate() {
+        let (x, y) = (i % s.w, i / s.w);
+        let c = match axis {
+            Axis::X => x,
+            Axis::Y => y,
+        };
+        if c == at {
+            continue;
+        }
+        let c = folded - c.abs_diff(at);
+        let j = match axis {
+            Axis::X => y * to.w + c,
+            Axis::Y => c * to.w + x,
+        };
+        cells[j] = Some(match &cells[j] {
+            Some(other) => merge(other, v),
+            None => v.clone(),
+        });
+    }
+    // Every distance up to the larger side has a cell, so nothing is left empty
+    Ok((to, cells.into_iter().map(|c| c.unwrap()).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::grid::*;
+    use crate::lib::{Point, Shape};
+
+    fn grid(rows: &[&str]) -> (Shape, Vec<char>) {
+        let shape = Shape::new(rows[0].len(), rows.len());
+        (shape, rows.iter().flat_map(|r| r.chars()).collect())
+    }
+
+    #[test]
+    fn test_orientation() {
+        let (s, img) = grid(&["abc", "def"]);
+        assert_eq!(rotate(&s, &img, 1), grid(&["da", "eb", "fc"]));
+        assert_eq!(rotate(&s, &img, 2), grid(&["fed", "cba"]));
+        assert_eq!(rotate(&s, &img, 3), grid(&["cf", "be", "ad"]));
+        assert_eq!(rotate(&s, &img, 4), (s, img.clone()));
+        assert_eq!(transpose(&s, &img), grid(&["ad", "be", "cf"]));
+        assert_eq!(mirror(&s, &img, Axis::X), grid(&["cba", "fed"]));
+        assert_eq!(mirror(&s, &img, Axis::Y), grid(&["def", "abc"]));
+    }
+
+    #[test]
+    fn test_resize() {
+        let (s, img) = grid(&["abc", "def"]);
+        let margins = Margins {
+            left: 1,
+            top: 0,
+            right: 2,
+            bottom: 1,
+        };
+        let (ps, padded) = pad(&s, &img, margins, '.');
+        assert_eq!((ps, padded.clone()), grid(&[".abc..", ".def..", "......"]));
+        assert_eq!(crop(&ps, &padded, margins), Ok((s, img.clone())));
+        assert_eq!(
+            sub_grid(&s, &img, Point::new(1, 1), Shape::new(2, 1)),
+            Ok(grid(&["ef"]))
+        );
+        assert!(sub_grid(&s, &img, Point::new(2, 0), Shape::new(2, 1)).is_err());
+        assert!(crop(&s, &img, Margins::uniform(2)).is_err());
+
+        let (s, digits) = (Shape::new(2, 1), vec![1u8, 9]);
+        let tiled = tile(&s, &digits, Shape::new(2, 2), |v, t| {
+            (v - 1 + (t.x + t.y) as u8) % 9 + 1
+        });
+        assert_eq!(tiled, (Shape::new(4, 2), vec![1, 9, 2, 1, 2, 1, 3, 2]));
+        let empty = tile(&Shape::new(0, 3), &digits[..0], Shape::new(2, 2), |v, _| *v);
+        assert_eq!(empty, (Shape::new(0, 6), vec![]));
+    }
+
+    #[test]
+    fn test_fold() {
+        let (s, img) = grid(&["#..|.#.", "...|..#"]);
+        let or = |a: &char, b: &char| if *a == '#' || *b == '#' { '#' } else { '.' };
+        assert_eq!(fold(&s, &img, Axis::X, 3, or), Ok(grid(&["##.", "#.."])));
+        assert_eq!(fold(&s, &img, Axis::Y, 0, or), Ok(grid(&["...|..#"])));
+        // Folding at the first column flips the grid
+        assert_eq!(
+            fold(&s, &img, Axis::X, 0, or),
+            Ok(grid(&[".#.|..", "#..|.."]))
+        );
+        assert_eq!(
+            fold(&s, &img, Axis::X, 6, or),
+            Ok(grid(&["#..|.#", "...|.."]))
+        );
+        assert!(fold(&s, &img, Axis::X, 7, or).is_err());
+        assert!(fold(&Shape::new(3, 1), &[1, 2, 3], Axis::X, 5, |a, _| *a).is_err());
+    }
+}
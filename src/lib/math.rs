@@ -0,0 +1,206 @@
+// Integer number theory. Intermediate products are taken in 128 bits so
+// nothing here overflows for any 64-bit input.
+
+// Euclidean remainder, always in 0..|m|. Panics when m is 0, like `%`.
+pub fn modulo(a: i64, m: i64) -> i64 {
+    (a as i128).rem_euclid(m as i128) as i64
+}
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// None when the result does not fit
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    match (a, b) {
+        (0, _) | (_, 0) => Some(0),
+        _ => (a / gcd(a, b)).checked_mul(b),
+    }
+}
+
+fn ext_gcd_wide(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1i128, 0i128);
+    let (mut y0, mut y1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (r0, x0, y0) = (-r0, -x0, -y0);
+    }
+    (r0, x0, y0)
+}
+
+// (g, x, y) with a * x + b * y = g = gcd(a, b), g never negative. None when g
+// is 2^63, i.e. for (MIN, MIN) and (MIN, 0).
+pub fn ext_gcd(a: i64, b: i64) -> Option<(i64, i64, i64)> {
+    let (g, x, y) = ext_gcd_wide(a as i128, b as i128);
+    Some((
+        i64::try_from(g).ok()?,
+        i64::try_from(x).ok()?,
+        i64::try_from(y).ok()?,
+    ))
+}
+
+// x in 0..|m| with a * x = 1 (mod m), if a and m are coprime
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    if m == 0 {
+        return None;
+    }
+    let (g, x, _) = ext_gcd_wide(modulo(a, m) as i128, m as i128);
+    match g {
+        1 => Some((x.rem_euclid((m as i128).abs())) as i64),
+        _ => None,
+    }
+}
+
+pub fn mod_mul(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+pub fn mod_pow(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut res = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mod_mul(res, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    res
+}
+
+// Solves x = r (mod m) for every (r, m), the moduli need not be coprime. Gives
+// (x, lcm of the moduli) with x in 0..lcm, None if the congruences contradict
+// each other, a modulus is 0 or the lcm does not fit in an i64. Everything is
+// done in i128, where |MIN| and products of two moduli still fit.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut x: i128 = 0;
+    let mut l: i128 = 1;
+    for &(r, m) in congruences {
+        if m == 0 {
+            return None;
+        }
+        let m = (m as i128).abs();
+        let (g, p, _) = ext_gcd_wide(l, m);
+        let diff = r as i128 - x;
+        if diff % g != 0 {
+            return None;
+        }
+        // x + l * k = r (mod m)  =>  k = diff / g * p (mod m / g)
+        let step = m / g;
+        let k = (diff / g % step * (p % step)).rem_euclid(step);
+        x += l * k;
+        l *= step;
+        if l > i64::MAX as i128 {
+            return None;
+        }
+        x = x.rem_euclid(l);
+    }
+    Some((x as i64, l as i64))
+}
+
+// Largest r with r * r <= n
+pub fn isqrt(n: u64) -> u64 {
+    let mut r = (n as f64).sqrt() as u64;
+    while r as u128 * r as u128 > n as u128 {
+        r -= 1;
+    }
+    while (r + 1) as u128 * (r + 1) as u128 <= n as u128 {
+        r += 1;
+    }
+    r
+}
+
+// Miller-Rabin with the first twelve primes as witnesses, which is exact below 2^64
+pub fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(p) = WITNESSES.iter().find(|p| n.is_multiple_of(**p)) {
+        return n == *p;
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    WITNESSES.iter().all(|a| {
+        let mut x = mod_pow(*a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mod_mul(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::math::*;
+
+    #[test]
+    fn test_modular() {
+        assert_eq!(modulo(-7, 3), 2);
+        assert_eq!(modulo(7, -3), 1);
+        assert_eq!(modulo(i64::MIN, -1), 0);
+        assert_eq!(gcd(84, 36), 12);
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+
+        let (g, x, y) = ext_gcd(240, -46).unwrap();
+        assert_eq!(g, 2);
+        assert_eq!(240 * x - 46 * y, 2);
+        let (g, x, y) = ext_gcd(i64::MIN, 3).unwrap();
+        assert_eq!(g, 1);
+        assert_eq!(i64::MIN as i128 * x as i128 + 3 * y as i128, 1);
+        assert_eq!(ext_gcd(i64::MIN, 0), None);
+        assert_eq!(ext_gcd(i64::MIN, i64::MIN), None);
+        assert_eq!(ext_gcd(i64::MIN, i64::MAX), Some((1, -1, -1)));
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(3, 0), None);
+        assert_eq!(mod_inverse(3, i64::MIN), Some(3074457345618258603));
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(
+            mod_pow(u64::MAX, u64::MAX, u64::MAX - 58),
+            4959809447704153900
+        );
+        assert_eq!(mod_pow(5, 0, 1), 0);
+
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(-1, 4), (1, 6)]), Some((7, 12)));
+        assert_eq!(crt(&[(0, 4), (1, 6)]), None);
+        assert_eq!(crt(&[(0, 1 << 40), (0, (1 << 40) - 1)]), None);
+        assert_eq!(crt(&[(2, 3), (1, 0)]), None);
+        assert_eq!(crt(&[(5, -7)]), Some((5, 7)));
+        assert_eq!(crt(&[(1, i64::MIN)]), None);
+        assert_eq!(crt(&[(i64::MIN, i64::MAX)]), Some((i64::MAX - 1, i64::MAX)));
+    }
+
+    #[test]
+    fn test_roots_and_primes() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(99), 9);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt((1 << 52) + 1), 1 << 26);
+
+        let small: Vec<u64> = (0..30).filter(|n| is_prime(*n)).collect();
+        assert_eq!(small, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(is_prime(u64::MAX - 58));
+        assert!(!is_prime(3215031751)); // strong pseudoprime to bases 2, 3, 5 and 7
+        assert!(!is_prime(u64::MAX));
+    }
+}
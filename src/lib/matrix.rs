@@ -0,0 +1,180 @@
+// Dense u64 matrices for linear recurrences: a state vector advanced by a linear
+// map `n` times is the map's matrix raised to the n-th power.
+use std::ops::Mul;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<u64>,
+}
+
+impl Matrix {
+    pub fn zero(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows,
+            cols,
+            data: vec![0; rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Matrix {
+        let mut m = Matrix::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, 1);
+        }
+        m
+    }
+
+    pub fn from_rows(rows: &[Vec<u64>]) -> Result<Matrix, String> {
+        let cols = rows.first().map_or(0, |r| r.len());
+        if rows.iter().any(|r| r.len() != cols) {
+            return Err("rows differ in length".to_string());
+        }
+        Ok(Matrix {
+            rows: rows.len(),
+            cols,
+            data: rows.concat(),
+        })
+    }
+
+    // Transition matrix of a linear `tick` on n-element states: column j is the
+    // image of the j-th unit vector. Non-linear ticks give meaningless results.
+    pub fn from_linear<F>(n: usize, tick: F) -> Matrix
+    where
+        F: Fn(&[u64]) -> Vec<u64>,
+    {
+        let mut m = Matrix::zero(n, n);
+        for j in 0..n {
+            let mut unit = vec![0; n];
+            unit[j] = 1;
+            let image = tick(&unit);
+            assert_eq!(image.len(), n, "tick changed the state size");
+            for (i, v) in image.into_iter().enumerate() {
+                m.set(i, j, v);
+            }
+        }
+        m
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> u64 {
+        self.data[i * self.cols + j]
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, v: u64) {
+        self.data[i * self.cols + j] = v;
+    }
+
+    // Sums in u128, then `reduce` brings every entry back into u64 (or fails)
+    fn product<F>(&self, other: &Matrix, reduce: F) -> Option<Matrix>
+    where
+        F: Fn(u128) -> Option<u64>,
+    {
+        assert_eq!(self.cols, other.rows, "matrix dimensions do not match");
+        let mut res = Matrix::zero(self.rows, other.cols);
+        for i in 0..self.rows {
+            for j in 0..other.cols {
+                let mut sum: u128 = 0;
+                for k in 0..self.cols {
+                    let term = self.get(i, k) as u128 * other.get(k, j) as u128;
+                    sum = reduce(sum.checked_add(term)?)? as u128;
+                }
+                res.set(i, j, sum as u64);
+            }
+        }
+        Some(res)
+    }
+
+    pub fn checked_mul(&self, other: &Matrix) -> Option<Matrix> {
+        self.product(other, |v| u64::try_from(v).ok())
+    }
+
+    // The *_mod variants reduce every entry, so the modulus must not be 0
+    pub fn mul_mod(&self, other: &Matrix, modulus: u64) -> Matrix {
+        self.product(other, |v| Some((v % modulus as u128) as u64))
+            .unwrap()
+    }
+
+    // Square-and-multiply, with `mul` one of the products above
+    fn pow_by<F>(&self, mut n: u64, mul: F) -> Option<Matrix>
+    where
+        F: Fn(&Matrix, &Matrix) -> Option<Matrix>,
+    {
+        assert_eq!(self.rows, self.cols, "only square matrices have powers");
+        let mut res = Matrix::identity(self.rows);
+        let mut base = self.clone();
+        while n > 0 {
+            if n & 1 == 1 {
+                res = mul(&res, &base)?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = mul(&base, &base)?;
+            }
+        }
+        Some(res)
+    }
+
+    pub fn checked_pow(&self, n: u64) -> Option<Matrix> {
+        self.pow_by(n, |a, b| a.checked_mul(b))
+    }
+
+    pub fn pow_mod(&self, n: u64, modulus: u64) -> Matrix {
+        let mut res = self.pow_by(n, |a, b| Some(a.mul_mod(b, modulus))).unwrap();
+        // The identity of a zero power is not reduced yet
+        res.data.iter_mut().for_each(|v| *v %= modulus);
+        res
+    }
+
+    pub fn checked_apply(&self, v: &[u64]) -> Option<Vec<u64>> {
+        let col = Matrix {
+            rows: v.len(),
+            cols: 1,
+            data: v.to_vec(),
+        };
+        self.checked_mul(&col).map(|m| m.data)
+    }
+
+    pub fn apply_mod(&self, v: &[u64], modulus: u64) -> Vec<u64> {
+        let col = Matrix {
+            rows: v.len(),
+            cols: 1,
+            data: v.to_vec(),
+        };
+        self.mul_mod(&col, modulus).data
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, other: &Matrix) -> Matrix {
+        self.checked_mul(other).expect("matrix product overflowed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::matrix::Matrix;
+
+    #[test]
+    fn test_fibonacci() {
+        let fib = Matrix::from_linear(2, |v| vec![v[1], v[0] + v[1]]);
+        assert_eq!(fib, Matrix::from_rows(&[vec![0, 1], vec![1, 1]]).unwrap());
+        assert_eq!(fib.checked_pow(90).unwrap().get(0, 1), 2880067194370816120);
+        assert_eq!(fib.checked_pow(100), None);
+        assert_eq!(fib.pow_mod(100, 1_000_000_007).get(0, 1), 687995182);
+        assert_eq!(fib.pow_mod(0, 1), Matrix::zero(2, 2));
+        assert_eq!(fib.checked_apply(&[0, 1]), Some(vec![1, 1]));
+        assert_eq!((&fib * &fib).apply_mod(&[0, 1], 2), vec![1, 0]);
+        assert!(Matrix::from_rows(&[vec![1], vec![1, 2]]).is_err());
+    }
+}
@@ -1,7 +1,25 @@
+pub mod bigint;
+pub mod combinatorics;
+pub mod counter;
+pub mod csp;
+pub mod graph;
+pub mod grid;
+pub mod math;
+pub mod matrix;
+pub mod neighbourhood;
+pub mod ocr;
+pub mod polymer;
+pub mod raster;
+pub mod search;
+pub mod space;
+
 use std::fmt::Display;
 use std::fs;
 use std::ops::{Div, Mul};
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
 
 pub fn read_lines(input_filename: &str) -> Vec<String> {
     let input = fs::read_to_string(input_filename).unwrap();
@@ -9,7 +27,7 @@ pub fn read_lines(input_filename: &str) -> Vec<String> {
     lines.collect()
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Shape {
     pub w: usize,
     pub h: usize,
@@ -41,6 +59,10 @@ impl Shape {
     pub fn len(self) -> usize {
         self.w * self.h
     }
+
+    pub fn adjacency(&self, include_diag: bool) -> Adjacency {
+        Adjacency::new(self, include_diag)
+    }
 }
 
 impl Mul<usize> for Shape {
@@ -119,6 +141,28 @@ pub fn bottom_right(s: &Shape, p: &Point) -> Option<Point> {
     bottom(s, &right(s, p)?)
 }
 
+// Wrapping variants treat the shape as a torus, leaving one edge re-enters on the other
+
+fn wrap(v: i64, len: usize) -> usize {
+    math::modulo(v, len as i64) as usize
+}
+
+pub fn left_wrap(s: &Shape, p: &Point) -> Point {
+    Point::new(wrap(p.x as i64 - 1, s.w), p.y)
+}
+
+pub fn right_wrap(s: &Shape, p: &Point) -> Point {
+    Point::new(wrap(p.x as i64 + 1, s.w), p.y)
+}
+
+pub fn top_wrap(s: &Shape, p: &Point) -> Point {
+    Point::new(p.x, wrap(p.y as i64 - 1, s.h))
+}
+
+pub fn bottom_wrap(s: &Shape, p: &Point) -> Point {
+    Point::new(p.x, wrap(p.y as i64 + 1, s.h))
+}
+
 pub fn point_to_i(s: &Shape, p: &Point) -> usize {
     p.x + s.w * p.y
 }
@@ -159,32 +203,95 @@ const NEIGHBOURS_DIAG: [fn(&Shape, &Point) -> Option<Point>; 8] = [
     bottom_right,
 ];
 
-pub fn neighbours(s: &Shape, i: usize, include_diag: bool) -> Vec<usize> {
-    let p = i_to_point(s, i);
+fn directions(include_diag: bool) -> &'static [fn(&Shape, &Point) -> Option<Point>] {
+    match include_diag {
+        false => &NEIGHBOURS_NOT_DIAG,
+        true => &NEIGHBOURS_DIAG,
+    }
+}
 
-    (if !include_diag {
-        NEIGHBOURS_NOT_DIAG.to_vec()
-    } else {
-        NEIGHBOURS_DIAG.to_vec()
-    })
-    .into_iter()
-    .filter_map(|f| f(s, &p))
-    .map(|p| point_to_i(s, &p))
-    .collect()
+// Iterates the neighbours inside the shape without allocating
+pub struct Neighbours {
+    s: Shape,
+    p: Point,
+    dirs: &'static [fn(&Shape, &Point) -> Option<Point>],
+    k: usize,
 }
 
-pub fn neighbours_2(s: &Shape, i: usize, include_diag: bool) -> Vec<Option<usize>> {
-    (if !include_diag {
-        NEIGHBOURS_NOT_DIAG.to_vec()
-    } else {
-        NEIGHBOURS_DIAG.to_vec()
-    })
-    .into_iter()
-    .map(|f| match f(s, &i_to_point(s, i)) {
-        Some(p) => Some(point_to_i(s, &p)),
-        None => None,
-    })
-    .collect()
+impl Iterator for Neighbours {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let f = self.dirs.get(self.k)?;
+            self.k += 1;
+            if let Some(p) = f(&self.s, &self.p) {
+                return Some(point_to_i(&self.s, &p));
+            }
+        }
+    }
+}
+
+pub fn neighbours(s: &Shape, i: usize, include_diag: bool) -> Neighbours {
+    Neighbours {
+        s: *s,
+        p: i_to_point(s, i),
+        dirs: directions(include_diag),
+        k: 0,
+    }
+}
+
+// Like Neighbours, but yields None in place of neighbours outside the shape
+pub struct NeighbourSlots {
+    s: Shape,
+    p: Point,
+    dirs: std::slice::Iter<'static, fn(&Shape, &Point) -> Option<Point>>,
+}
+
+impl Iterator for NeighbourSlots {
+    type Item = Option<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let f = self.dirs.next()?;
+        Some(f(&self.s, &self.p).map(|p| point_to_i(&self.s, &p)))
+    }
+}
+
+pub fn neighbours_2(s: &Shape, i: usize, include_diag: bool) -> NeighbourSlots {
+    NeighbourSlots {
+        s: *s,
+        p: i_to_point(s, i),
+        dirs: directions(include_diag).iter(),
+    }
+}
+
+// Neighbour lists of every cell computed once, for hot loops over a fixed shape
+#[derive(Debug, Clone)]
+pub struct Adjacency {
+    start: Vec<usize>,
+    cells: Vec<usize>,
+}
+
+impl Adjacency {
+    pub fn new(s: &Shape, include_diag: bool) -> Adjacency {
+        let mut start = Vec::with_capacity(s.len() + 1);
+        let mut cells = Vec::with_capacity(s.len() * directions(include_diag).len());
+        start.push(0);
+        for i in 0..s.len() {
+            cells.extend(neighbours(s, i, include_diag));
+            start.push(cells.len());
+        }
+        Adjacency { start, cells }
+    }
+
+    pub fn get(&self, i: usize) -> &[usize] {
+        &self.cells[self.start[i]..self.start[i + 1]]
+    }
+
+    // Number of cells in the shape
+    pub fn cells(&self) -> usize {
+        self.start.len() - 1
+    }
 }
 
 pub fn map_i(from: &Shape, to: &Shape, offset: &Shape, i: usize) -> usize {
@@ -198,16 +305,7 @@ pub fn pad<T>(s: &Shape, img: &[T], pad_by: usize, padder: T) -> (Shape, Vec<T>)
 where
     T: Clone + Copy,
 {
-    let new_shape = Shape::new(s.w + 2 * pad_by, s.h + 2 * pad_by);
-    let mut new_image: Vec<T> = vec![padder; new_shape.len()];
-    let offset = Shape::new(pad_by, pad_by);
-
-    for i in 0..s.len() {
-        let j = map_i(s, &new_shape, &offset, i);
-        new_image[j] = img[i];
-    }
-
-    (new_shape, new_image)
+    grid::pad(s, img, grid::Margins::uniform(pad_by), padder)
 }
 
 pub fn print_image<T>(s: &Shape, img: &[T])
@@ -261,21 +359,163 @@ impl PartialOrd for DijkstraState {
     }
 }
 
+// How dijkstra keeps its frontier. Buckets (Dial's algorithm) needs every step
+// to cost at most `max_cost`, ZeroOne (0-1 BFS) only allows steps of 0 or 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Frontier {
+    Heap,
+    Buckets { max_cost: usize },
+    ZeroOne,
+}
+
+trait Queue {
+    fn push(&mut self, state: DijkstraState);
+    fn pop(&mut self) -> Option<DijkstraState>;
+}
+
+impl Queue for BinaryHeap<DijkstraState> {
+    fn push(&mut self, state: DijkstraState) {
+        BinaryHeap::push(self, state)
+    }
+
+    fn pop(&mut self) -> Option<DijkstraState> {
+        BinaryHeap::pop(self)
+    }
+}
+
+// Ring of max_cost + 1 buckets, the frontier never spans more cost than that
+struct Buckets {
+    buckets: Vec<Vec<usize>>,
+    cost: usize,
+    len: usize,
+}
+
+impl Queue for Buckets {
+    fn push(&mut self, state: DijkstraState) {
+        let n = self.buckets.len();
+        assert!(
+            state.cost - self.cost < n,
+            "step cost above the bucket limit of {}",
+            n - 1
+        );
+        self.buckets[state.cost % n].push(state.position);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<DijkstraState> {
+        if self.len == 0 {
+            return None;
+        }
+        let n = self.buckets.len();
+        loop {
+            if let Some(position) = self.buckets[self.cost % n].pop() {
+                self.len -= 1;
+                return Some(DijkstraState {
+                    cost: self.cost,
+                    position,
+                });
+            }
+            self.cost += 1;
+        }
+    }
+}
+
+// Zero cost steps go to the front, unit steps to the back
+struct ZeroOne {
+    deque: VecDeque<DijkstraState>,
+    cost: usize,
+}
+
+impl Queue for ZeroOne {
+    fn push(&mut self, state: DijkstraState) {
+        match state.cost - self.cost {
+            0 => self.deque.push_front(state),
+            1 => self.deque.push_back(state),
+            _ => panic!("0-1 BFS step cost above 1"),
+        }
+    }
+
+    fn pop(&mut self) -> Option<DijkstraState> {
+        let state = self.deque.pop_front()?;
+        self.cost = state.cost;
+        Some(state)
+    }
+}
+
 pub fn dijkstra<F>(shape: &Shape, start: usize, target: usize, cost_fn: F) -> Vec<usize>
 where
     F: Fn(usize) -> usize,
 {
-    let mut heap: BinaryHeap<DijkstraState> = BinaryHeap::new();
+    dijkstra_by(
+        shape,
+        start,
+        target,
+        |u| neighbours(shape, u, false),
+        cost_fn,
+        Frontier::Heap,
+    )
+}
+
+// Dijkstra with the neighbour lookup supplied, e.g. from a precomputed Adjacency,
+// and a frontier suited to the step costs
+pub fn dijkstra_by<N, I, F>(
+    shape: &Shape,
+    start: usize,
+    target: usize,
+    neighbours: N,
+    cost_fn: F,
+    frontier: Frontier,
+) -> Vec<usize>
+where
+    N: Fn(usize) -> I,
+    I: IntoIterator<Item = usize>,
+    F: Fn(usize) -> usize,
+{
+    let (n, c) = (&neighbours, &cost_fn);
+    match frontier {
+        Frontier::Heap => run_dijkstra(shape, start, target, n, c, BinaryHeap::new()),
+        Frontier::Buckets { max_cost } => {
+            let buckets = Buckets {
+                buckets: vec![vec![]; max_cost + 1],
+                cost: 0,
+                len: 0,
+            };
+            run_dijkstra(shape, start, target, n, c, buckets)
+        }
+        Frontier::ZeroOne => {
+            let deque = ZeroOne {
+                deque: VecDeque::new(),
+                cost: 0,
+            };
+            run_dijkstra(shape, start, target, n, c, deque)
+        }
+    }
+}
+
+fn run_dijkstra<N, I, F, Q>(
+    shape: &Shape,
+    start: usize,
+    target: usize,
+    neighbours: &N,
+    cost_fn: &F,
+    mut queue: Q,
+) -> Vec<usize>
+where
+    Q: Queue,
+    N: Fn(usize) -> I,
+    I: IntoIterator<Item = usize>,
+    F: Fn(usize) -> usize,
+{
     let mut dist: Vec<_> = (0..shape.len()).map(|_| usize::MAX).collect();
 
     dist[start] = 0;
 
-    heap.push(DijkstraState {
+    queue.push(DijkstraState {
         cost: 0,
         position: start,
     });
 
-    while let Some(DijkstraState { cost, position: u }) = heap.pop() {
+    while let Some(DijkstraState { cost, position: u }) = queue.pop() {
         if u == target {
             break;
         }
@@ -284,22 +524,18 @@ where
             continue;
         }
 
-        for v in neighbours(&shape, u, false) {
+        for v in neighbours(u) {
             let next = DijkstraState {
                 cost: cost + cost_fn(v),
                 position: v,
             };
 
             if next.cost < dist[next.position] {
-                heap.push(next);
+                queue.push(next);
                 dist[next.position] = next.cost;
             }
         }
     }
 
-    return dist;
-}
-
-pub fn modulo(a: usize, b: usize) -> usize {
-    ((a % b) + b) % b
+    dist
 }
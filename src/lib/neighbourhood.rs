@@ -0,0 +1,293 @@
+// Neighbourhood strategies over cells stored as dense indices, so the same BFS
+// and cellular automaton code runs on square, toroidal, hex and N-d grids.
+use std::collections::VecDeque;
+
+use crate::lib::{math, Adjacency};
+
+pub trait Neighbourhood {
+    type Iter<'a>: Iterator<Item = usize>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+
+    fn neighbours(&self, i: usize) -> Self::Iter<'_>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Box of cells in D dimensions, first axis varying fastest (x, then y, ...).
+// Neighbours are fixed coordinate offsets, which either stop at the edges or
+// wrap around them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lattice<const D: usize> {
+    dims: [usize; D],
+    offsets: Vec<[isize; D]>,
+    wrap: bool,
+}
+
+// Every offset in {-1, 0, 1}^D except the origin
+fn unit_offsets<const D: usize>() -> Vec<[isize; D]> {
+    (0..3usize.pow(D as u32))
+        .map(|mut n| {
+            let mut o = [0; D];
+            for v in o.iter_mut() {
+                *v = (n % 3) as isize - 1;
+                n /= 3;
+            }
+            o
+        })
+        .filter(|o| o.iter().any(|v| *v != 0))
+        .collect()
+}
+
+impl<const D: usize> Lattice<D> {
+    pub fn new(dims: [usize; D], offsets: Vec<[isize; D]>) -> Lattice<D> {
+        Lattice {
+            dims,
+            offsets,
+            wrap: false,
+        }
+    }
+
+    // Cells sharing a face: 4 in 2D, 6 in 3D, 8 in 4D
+    pub fn von_neumann(dims: [usize; D]) -> Lattice<D> {
+        let offsets = unit_offsets()
+            .into_iter()
+            .filter(|o| o.iter().filter(|v| **v != 0).count() == 1)
+            .collect();
+        Lattice::new(dims, offsets)
+    }
+
+    // Cells sharing a face, edge or corner: 8 in 2D, 26 in 3D, 80 in 4D
+    pub fn moore(dims: [usize; D]) -> Lattice<D> {
+        Lattice::new(dims, unit_offsets())
+    }
+
+    // Toroidal: leaving one edge re-enters on the opposite one. Along a dimension of
+    // size 1 or 2 several offsets land on the same cell, or on the cell itself;
+    // neighbours() yields each such cell once and skips the cell itself.
+    pub fn wrapping(mut self) -> Lattice<D> {
+        self.wrap = true;
+        self
+    }
+
+    pub fn dims(&self) -> [usize; D] {
+        self.dims
+    }
+
+    pub fn index(&self, coord: [usize; D]) -> usize {
+        (0..D).rev().fold(0, |i, d| i * self.dims[d] + coord[d])
+    }
+
+    pub fn coord(&self, mut i: usize) -> [usize; D] {
+        let mut coord = [0; D];
+        for (c, dim) in coord.iter_mut().zip(self.dims) {
+            *c = i % dim;
+            i /= dim;
+        }
+        coord
+    }
+
+    // Cell at the offset from i, None if it falls off a non-wrapping lattice
+    pub fn step(&self, i: usize, offset: [isize; D]) -> Option<usize> {
+        let mut coord = self.coord(i);
+        for d in 0..D {
+            let v = coord[d] as i64 + offset[d] as i64;
+            coord[d] = match self.wrap {
+                true => math::modulo(v, self.dims[d] as i64) as usize,
+                false if (0..self.dims[d] as i64).contains(&v) => v as usize,
+                false => return None,
+            };
+        }
+        Some(self.index(coord))
+    }
+
+    // One slot per offset, None where the neighbour is off the lattice
+    pub fn neighbour_slots(&self, i: usize) -> impl Iterator<Item = Option<usize>> + '_ {
+        self.offsets.iter().map(move |o| self.step(i, *o))
+    }
+}
+
+impl Lattice<2> {
+    // Hex grid in axial coordinates (q, r), stored as a w * h parallelogram
+    pub fn hex(w: usize, h: usize) -> Lattice<2> {
+        let offsets = vec![[1, 0], [1, -1], [0, -1], [-1, 0], [-1, 1], [0, 1]];
+        Lattice::new([w, h], offsets)
+    }
+
+    // Steps between two hex cells in axial coordinates
+    pub fn hex_distance(a: [usize; 2], b: [usize; 2]) -> usize {
+        let dq = a[0] as isize - b[0] as isize;
+        let dr = a[1] as isize - b[1] as isize;
+        (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+    }
+}
+
+pub struct LatticeNeighbours<'a, const D: usize> {
+    lattice: &'a Lattice<D>,
+    i: usize,
+    k: usize,
+}
+
+impl<const D: usize> LatticeNeighbours<'_, D> {
+    // Only a wrapping lattice with a dimension under 3 can reach a cell twice
+    fn seen(&self, j: usize) -> bool {
+        let lattice = self.lattice;
+        if !lattice.wrap || lattice.dims.iter().all(|d| *d > 2) {
+            return false;
+        }
+        j == self.i
+            || lattice.offsets[..self.k - 1]
+                .iter()
+                .any(|o| lattice.step(self.i, *o) == Some(j))
+    }
+}
+
+impl<const D: usize> Iterator for LatticeNeighbours<'_, D> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let o = self.lattice.offsets.get(self.k)?;
+            self.k += 1;
+            match self.lattice.step(self.i, *o) {
+                Some(j) if !self.seen(j) => return Some(j),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<const D: usize> Neighbourhood for Lattice<D> {
+    type Iter<'a> = LatticeNeighbours<'a, D>;
+
+    fn len(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    fn neighbours(&self, i: usize) -> Self::Iter<'_> {
+        LatticeNeighbours {
+            lattice: self,
+            i,
+            k: 0,
+        }
+    }
+}
+
+impl Neighbourhood for Adjacency {
+    type Iter<'a> = std::iter::Copied<std::slice::Iter<'a, usize>>;
+
+    fn len(&self) -> usize {
+        self.cells()
+    }
+
+    fn neighbours(&self, i: usize) -> Self::Iter<'_> {
+        self.get(i).iter().copied()
+    }
+}
+
+// Steps from start to every cell reachable through cells that pass `open`
+pub fn bfs<N, F>(n: &N, start: usize, open: F) -> Vec<Option<usize>>
+where
+    N: Neighbourhood,
+    F: Fn(usize) -> bool,
+{
+    let mut dist = vec![None; n.len()];
+    dist[start] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(u) = queue.pop_front() {
+        let d = dist[u].unwrap() + 1;
+        for v in n.neighbours(u) {
+            if dist[v].is_none() && open(v) {
+                dist[v] = Some(d);
+                queue.push_back(v);
+            }
+        }
+    }
+    dist
+}
+
+// One generation of a cellular automaton: every cell is replaced by the rule
+// applied to it and its neighbours, all read from the previous generation
+pub fn automaton_step<N, T, F>(n: &N, cells: &[T], rule: F) -> Vec<T>
+where
+    N: Neighbourhood,
+    F: Fn(&T, &mut dyn Iterator<Item = &T>) -> T,
+{
+    (0..n.len())
+        .map(|i| rule(&cells[i], &mut n.neighbours(i).map(|j| &cells[j])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::neighbourhood::{automaton_step, bfs, Lattice, Neighbourhood};
+    use crate::lib::{bottom_wrap, left_wrap, right_wrap, top_wrap, Point, Shape};
+
+    #[test]
+    fn test_lattices() {
+        let square = Lattice::von_neumann([4, 3]);
+        assert_eq!(square.neighbours(0).collect::<Vec<_>>(), [1, 4]);
+        let torus = square.clone().wrapping();
+        assert_eq!(torus.neighbours(0).count(), 4);
+        assert_eq!(torus.step(0, [-1, 0]), Some(3));
+        assert_eq!(torus.step(11, [0, 1]), Some(3));
+        assert_eq!(square.step(11, [0, 1]), None);
+
+        // Left and right meet on a width of 2, a height of 1 wraps onto the cell itself
+        let thin = Lattice::von_neumann([2, 1]).wrapping();
+        assert_eq!(thin.neighbours(0).collect::<Vec<_>>(), [1]);
+        assert_eq!(
+            thin.neighbour_slots(0).collect::<Vec<_>>(),
+            [Some(0), Some(1), Some(1), Some(0)]
+        );
+        let ring = Lattice::moore([2, 2]).wrapping();
+        assert_eq!(ring.neighbours(0).collect::<Vec<_>>(), [3, 2, 1]);
+
+        let shape = Shape::new(4, 3);
+        assert_eq!(left_wrap(&shape, &Point::new(0, 1)), Point::new(3, 1));
+        assert_eq!(right_wrap(&shape, &Point::new(3, 1)), Point::new(0, 1));
+        assert_eq!(top_wrap(&shape, &Point::new(2, 0)), Point::new(2, 2));
+        assert_eq!(bottom_wrap(&shape, &Point::new(2, 2)), Point::new(2, 0));
+        assert_eq!(right_wrap(&shape, &Point::new(1, 1)), Point::new(2, 1));
+
+        assert_eq!(Lattice::moore([5, 5, 5]).neighbours(62).count(), 26);
+        assert_eq!(Lattice::moore([3, 3, 3, 3]).neighbours(40).count(), 80);
+        assert_eq!(Lattice::von_neumann([3, 3, 3, 3]).neighbours(40).count(), 8);
+        let cube = Lattice::von_neumann([2, 3, 4]);
+        assert_eq!(cube.coord(cube.index([1, 2, 3])), [1, 2, 3]);
+
+        let hex = Lattice::hex(5, 5);
+        assert_eq!(hex.neighbours(hex.index([2, 2])).count(), 6);
+        assert_eq!(hex.neighbour_slots(0).flatten().count(), 2);
+        let dist = bfs(&hex, hex.index([0, 4]), |_| true);
+        assert_eq!(dist[hex.index([4, 0])], Some(4));
+        assert_eq!(Lattice::hex_distance([0, 4], [4, 0]), 4);
+        assert_eq!(
+            dist[hex.index([4, 4])],
+            Some(Lattice::hex_distance([0, 4], [4, 4]))
+        );
+    }
+
+    #[test]
+    fn test_automaton() {
+        // Blinker on a torus flips between a row and a column
+        let torus = Lattice::moore([5, 5]).wrapping();
+        let life = |alive: &bool, around: &mut dyn Iterator<Item = &bool>| {
+            let n = around.filter(|a| **a).count();
+            n == 3 || (*alive && n == 2)
+        };
+        let mut cells = vec![false; 25];
+        for x in 1..4 {
+            cells[torus.index([x, 2])] = true;
+        }
+        let next = automaton_step(&torus, &cells, life);
+        let column: Vec<usize> = (1..4).map(|y| torus.index([2, y])).collect();
+        assert!(column.iter().all(|i| next[*i]));
+        assert_eq!(next.iter().filter(|a| **a).count(), 3);
+        assert_eq!(automaton_step(&torus, &next, life), cells);
+    }
+}
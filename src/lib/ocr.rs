@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use crate::lib::{Point, Shape};
+
+// Glyphs as drawn by Advent of Code puzzles, rows joined top to bottom
+const SMALL_HEIGHT: usize = 6;
+const SMALL_GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const LARGE_HEIGHT: usize = 10;
+const LARGE_GLYPHS: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+// Reads block letters from lit points. Letters are separated by empty columns,
+// the letter height picks the alphabet.
+pub fn recognize_points(points: &[Point]) -> Result<String, String> {
+    if points.is_empty() {
+        return Ok(String::new());
+    }
+
+    let x0 = points.iter().map(|p| p.x).min().unwrap();
+    let y0 = points.iter().map(|p| p.y).min().unwrap();
+    let lit: HashSet<Point> = points
+        .iter()
+        .map(|p| Point::new(p.x - x0, p.y - y0))
+        .collect();
+    let shape = Shape::new_from_points(&lit.iter().copied().collect());
+
+    let glyphs: &[(char, &str)] = match shape.h {
+        SMALL_HEIGHT => &SMALL_GLYPHS,
+        LARGE_HEIGHT => &LARGE_GLYPHS,
+        h => return Err(format!("no alphabet for letters {} pixels high", h)),
+    };
+
+    let column_lit = |x: usize| (0..shape.h).any(|y| lit.contains(&Point::new(x, y)));
+    let mut text = String::new();
+    let mut unrecognised: Vec<usize> = vec![];
+    let mut x = 0;
+
+    while x < shape.w {
+        if !column_lit(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < shape.w && column_lit(x) {
+            x += 1;
+        }
+
+        let pattern: Vec<String> = (0..shape.h)
+            .map(|y| {
+                (start..x)
+                    .map(|gx| match lit.contains(&Point::new(gx, y)) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+
+        match glyphs.iter().find(|(_, g)| *g == pattern.join("\n")) {
+            Some((c, _)) => text.push(*c),
+            None => {
+                text.push('?');
+                unrecognised.push(x0 + start);
+            }
+        }
+    }
+
+    match unrecognised.is_empty() {
+        true => Ok(text),
+        false => Err(format!(
+            "unrecognised glyphs at x={} in \"{}\"",
+            unrecognised
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", x="),
+            text
+        )),
+    }
+}
+
+pub fn recognize_grid(shape: &Shape, cells: &[bool]) -> Result<String, String> {
+    let points: Vec<Point> = (0..shape.len())
+        .filter(|i| cells[*i])
+        .map(|i| crate::lib::i_to_point(shape, i))
+        .collect();
+    recognize_points(&points)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::ocr::{recognize_grid, recognize_points};
+    use crate::lib::{Point, Shape};
+
+    fn parse(rows: &[&str]) -> (Shape, Vec<bool>) {
+        let shape = Shape::new(rows[0].len(), rows.len());
+        let cells = rows.iter().flat_map(|r| r.chars().map(|c| c == '#'));
+        (shape, cells.collect())
+    }
+
+    #[test]
+    fn test_small() {
+        let (shape, cells) = parse(&[
+            "#..#.####.###..#...#",
+            "#..#.#....#..#.#...#",
+            "####.###..###...#.#.",
+            "#..#.#....#..#...#..",
+            "#..#.#....#..#...#..",
+            "#..#.####.###....#..",
+        ]);
+        assert_eq!(recognize_grid(&shape, &cells), Ok("HEBY".to_string()));
+    }
+
+    #[test]
+    fn test_large() {
+        let (shape, cells) = parse(&[
+            "#....#..#....#",
+            "#....#..##...#",
+            ".#..#...##...#",
+            ".#..#...#.#..#",
+            "..##....#.#..#",
+            "..##....#..#.#",
+            ".#..#...#..#.#",
+            ".#..#...#...##",
+            "#....#..#...##",
+            "#....#..#....#",
+        ]);
+        assert_eq!(recognize_grid(&shape, &cells), Ok("XN".to_string()));
+    }
+
+    #[test]
+    fn test_unrecognised() {
+        let mut points: Vec<Point> = (0..5)
+            .flat_map(|i| [(i, 0), (0, i), (4, i), (i, 4), (i, 5)])
+            .map(|(x, y)| Point::new(x + 3, y))
+            .collect();
+        points.extend([Point::new(9, 0), Point::new(9, 5)]);
+        assert_eq!(
+            recognize_points(&points),
+            Err("unrecognised glyphs at x=3, x=9 in \"??\"".to_string())
+        );
+    }
+}
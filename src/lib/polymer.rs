@@ -0,0 +1,173 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::lib::counter::Counter;
+
+pub type Pair = (char, char);
+
+// Pair insertion rules `AB -> C`: every step inserts C between each adjacent A, B
+#[derive(Debug, Clone, Default)]
+pub struct PairRules {
+    rules: HashMap<Pair, char>,
+}
+
+// Polymer tracked as adjacent pair counts, so its length may grow exponentially.
+// The first and last elements never change and anchor the element histogram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairCounts {
+    first: Option<char>,
+    pairs: HashMap<Pair, usize>,
+}
+
+fn pairs(s: &str) -> impl Iterator<Item = Pair> + '_ {
+    s.chars().zip(s.chars().skip(1))
+}
+
+impl PairRules {
+    pub fn parse(lines: &[String]) -> Result<PairRules, String> {
+        let mut rules = HashMap::new();
+        for line in lines.iter().filter(|l| l.contains("->")) {
+            let (pair, insert) = line.split_once("->").unwrap();
+            let pair: Vec<char> = pair.trim().chars().collect();
+            let insert: Vec<char> = insert.trim().chars().collect();
+            match (&pair[..], &insert[..]) {
+                ([a, b], [c]) => rules.insert((*a, *b), *c),
+                _ => return Err(format!("invalid rule \"{}\"", line)),
+            };
+        }
+        Ok(PairRules { rules })
+    }
+
+    pub fn get(&self, pair: &Pair) -> Option<char> {
+        self.rules.get(pair).copied()
+    }
+
+    // Pairs over every element in the template or the rules that have no rule
+    pub fn missing_pairs(&self, template: &str) -> Vec<Pair> {
+        let elements: BTreeSet<char> = template
+            .chars()
+            .chain(self.rules.iter().flat_map(|((a, b), c)| [*a, *b, *c]))
+            .collect();
+        elements
+            .iter()
+            .flat_map(|a| elements.iter().map(move |b| (*a, *b)))
+            .filter(|p| !self.rules.contains_key(p))
+            .collect()
+    }
+
+    pub fn check_coverage(&self, template: &str) -> Result<(), String> {
+        match self.missing_pairs(template)[..] {
+            [] => Ok(()),
+            ref missing => Err(format!(
+                "no rule for {}",
+                missing
+                    .iter()
+                    .map(|(a, b)| format!("{}{}", a, b))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    // Builds the actual polymer, only feasible for a handful of steps
+    pub fn expand(&self, template: &str) -> String {
+        let mut res: String = template.chars().take(1).collect();
+        for pair in pairs(template) {
+            if let Some(c) = self.get(&pair) {
+                res.push(c);
+            }
+            res.push(pair.1);
+        }
+        res
+    }
+
+    pub fn expand_n(&self, template: &str, steps: usize) -> String {
+        (0..steps).fold(template.to_string(), |s, _| self.expand(&s))
+    }
+
+    // Element histogram of the polymer after each step, starting with the template
+    pub fn histograms(&self, template: &str, steps: usize) -> Vec<Counter<char>> {
+        let mut counts = PairCounts::new(template);
+        let mut res = vec![counts.histogram()];
+        for _ in 0..steps {
+            counts = counts.step(self);
+            res.push(counts.histogram());
+        }
+        res
+    }
+}
+
+impl PairCounts {
+    pub fn new(template: &str) -> PairCounts {
+        let mut counts = PairCounts {
+            first: template.chars().next(),
+            pairs: HashMap::new(),
+        };
+        for pair in pairs(template) {
+            *counts.pairs.entry(pair).or_default() += 1;
+        }
+        counts
+    }
+
+    // Each pair with a rule splits into two new pairs, others carry over as is
+    pub fn step(&self, rules: &PairRules) -> PairCounts {
+        let mut pairs: HashMap<Pair, usize> = HashMap::new();
+        for (pair, count) in &self.pairs {
+            match rules.get(pair) {
+                Some(c) => {
+                    *pairs.entry((pair.0, c)).or_default() += count;
+                    *pairs.entry((c, pair.1)).or_default() += count;
+                }
+                None => *pairs.entry(*pair).or_default() += count,
+            }
+        }
+        PairCounts {
+            first: self.first,
+            pairs,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.first.is_some() as usize + self.pairs.values().sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_none()
+    }
+
+    // Counting the second element of every pair covers all but the first element
+    pub fn histogram(&self) -> Counter<char> {
+        let mut h: Counter<char> = self.first.into_iter().collect();
+        for ((_, b), count) in &self.pairs {
+            h.add_n(*b, *count);
+        }
+        h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::polymer::{PairCounts, PairRules};
+
+    #[test]
+    fn test_expand() {
+        let lines: Vec<String> = ["AB -> C", "CB -> A", "AC -> A"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let rules = PairRules::parse(&lines).unwrap();
+
+        assert_eq!(rules.expand("AB"), "ACB");
+        assert_eq!(rules.expand_n("AB", 2), "AACAB");
+
+        let expanded = rules.expand_n("ABB", 5);
+        let counts = (0..5).fold(PairCounts::new("ABB"), |c, _| c.step(&rules));
+        assert_eq!(counts.len(), expanded.len());
+        let h = counts.histogram();
+        assert_eq!(h[&'A'], expanded.chars().filter(|c| *c == 'A').count());
+        assert_eq!(rules.histograms("ABB", 5).last(), Some(&h));
+
+        assert!(rules.check_coverage("AB").is_err());
+        assert_eq!(rules.missing_pairs("AB").len(), 6);
+        assert!(PairRules::parse(&["ABC -> D".to_string()]).is_err());
+    }
+}
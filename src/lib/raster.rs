@@ -0,0 +1,453 @@
+// Integer line segments: rasterisation, intersection and overlap counting
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub from: (isize, isize),
+    pub to: (isize, isize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Intersection {
+    Point(isize, isize),
+    Overlap(Segment), // collinear segments sharing a stretch
+}
+
+// Bresenham's line algorithm, visits every cell from `from` to `to` inclusive
+pub struct Raster {
+    x: isize,
+    y: isize,
+    to: (isize, isize),
+    dx: i128, // wide enough for segments spanning the whole isize range
+    dy: i128,
+    sx: isize,
+    sy: isize,
+    err: i128,
+    done: bool,
+}
+
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn dot(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn sub(a: (isize, isize), b: (isize, isize)) -> (i128, i128) {
+    (a.0 as i128 - b.0 as i128, a.1 as i128 - b.1 as i128)
+}
+
+impl Segment {
+    pub fn new(from: (isize, isize), to: (isize, isize)) -> Segment {
+        Segment { from, to }
+    }
+
+    // `x1,y1 -> x2,y2`
+    pub fn parse(s: &str) -> Result<Segment, String> {
+        let coords: Vec<isize> = s
+            .split("->")
+            .flat_map(|p| p.split(','))
+            .map(|v| v.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid segment \"{}\"", s))?;
+        match coords[..] {
+            [x1, y1, x2, y2] => Ok(Segment::new((x1, y1), (x2, y2))),
+            _ => Err(format!("invalid segment \"{}\"", s)),
+        }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.from.1 == self.to.1
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.from.0 == self.to.0
+    }
+
+    pub fn is_axis_aligned(&self) -> bool {
+        self.is_horizontal() || self.is_vertical()
+    }
+
+    pub fn points(&self) -> Raster {
+        let (dx, dy) = sub(self.to, self.from);
+        let (dx, dy) = (dx.abs(), -dy.abs());
+        Raster {
+            x: self.from.0,
+            y: self.from.1,
+            to: self.to,
+            dx,
+            dy,
+            sx: self.to.0.cmp(&self.from.0) as isize,
+            sy: self.to.1.cmp(&self.from.1) as isize,
+            err: dx + dy,
+            done: false,
+        }
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.from == self.to
+    }
+
+    fn contains(&self, p: (isize, isize)) -> bool {
+        if self.is_point() {
+            return p == self.from;
+        }
+        let d = sub(self.to, self.from);
+        let v = sub(p, self.from);
+        cross(d, v) == 0 && (0..=dot(d, d)).contains(&dot(d, v))
+    }
+
+    // Geometric intersection. Crossings that fall between integer coordinates
+    // are not reported, as no cell is shared there.
+    pub fn intersection(&self, other: &Segment) -> Option<Intersection> {
+        // A point has no direction to cross or overlap along
+        for (p, s) in [(self, other), (other, self)] {
+            if p.is_point() {
+                return s
+                    .contains(p.from)
+                    .then_some(Intersection::Point(p.from.0, p.from.1));
+            }
+        }
+
+        let d1 = sub(self.to, self.from);
+        let d2 = sub(other.to, other.from);
+        let q = sub(other.from, self.from);
+        let denom = cross(d1, d2);
+
+        if denom == 0 {
+            if cross(q, d1) != 0 {
+                return None; // parallel
+            }
+            // Collinear, the shared stretch runs between two of the four endpoints
+            let mut ends: Vec<(isize, isize)> = [self.from, self.to, other.from, other.to]
+                .into_iter()
+                .filter(|p| self.contains(*p) && other.contains(*p))
+                .collect();
+            ends.sort_by_key(|p| dot(sub(*p, self.from), d1));
+            ends.dedup();
+            return match ends[..] {
+                [] => None,
+                [p] => Some(Intersection::Point(p.0, p.1)),
+                [a, .., b] => Some(Intersection::Overlap(Segment::new(a, b))),
+            };
+        }
+
+        let (mut t, mut u, mut denom) = (cross(q, d2), cross(q, d1), denom);
+        if denom < 0 {
+            (t, u, denom) = (-t, -u, -denom);
+        }
+        if !(0..=denom).contains(&t) || !(0..=denom).contains(&u) {
+            return None;
+        }
+        if (d1.0 * t) % denom != 0 || (d1.1 * t) % denom != 0 {
+            return None;
+        }
+        Some(Intersection::Point(
+            (self.from.0 as i128 + d1.0 * t / denom) as isize,
+            (self.from.1 as i128 + d1.1 * t / denom) as isize,
+        ))
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{} -> {},{}",
+            self.from.0, self.from.1, self.to.0, self.to.1
+        )
+    }
+}
+
+impl Iterator for Raster {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let p = (self.x, self.y);
+        if p == self.to {
+            self.done = true;
+            return Some(p);
+        }
+        let e2 = 2 * self.err;
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.x += self.sx;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.y += self.sy;
+        }
+        Some(p)
+    }
+}
+
+// Bounding boxes up to this many cells are counted on a dense grid
+const DENSE_LIMIT: u128 = 1 << 24;
+
+// Lattice lines a run of cells can lie on, keyed by what is constant along them
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Line {
+    Row(i128),          // y
+    Column(i128),       // x
+    Diagonal(i128),     // y - x
+    AntiDiagonal(i128), // y + x
+}
+
+// The line a horizontal, vertical or 45 degree segment lies on, and the range
+// it covers along it: x, or y for columns. Single cells count as rows.
+fn line_of(s: &Segment) -> (Line, i128, i128) {
+    let (x0, y0) = (s.from.0 as i128, s.from.1 as i128);
+    let (x1, y1) = (s.to.0 as i128, s.to.1 as i128);
+    let line = match (x1 - x0, y1 - y0) {
+        (_, 0) => Line::Row(y0),
+        (0, _) => return (Line::Column(x0), y0.min(y1), y0.max(y1)),
+        (dx, dy) if dx == dy => Line::Diagonal(y0 - x0),
+        _ => Line::AntiDiagonal(y0 + x0),
+    };
+    (line, x0.min(x1), x0.max(x1))
+}
+
+// Every line through a cell, with the cell's position along it
+fn lines_through((x, y): (isize, isize)) -> [(Line, i128); 4] {
+    let (x, y) = (x as i128, y as i128);
+    [
+        (Line::Row(y), x),
+        (Line::Column(x), y),
+        (Line::Diagonal(y - x), x),
+        (Line::AntiDiagonal(y + x), x),
+    ]
+}
+
+// Splits a rasterised segment into runs of cells that each lie on one Line.
+// Horizontal, vertical and 45 degree segments are a single run and are not
+// rasterised; other slopes are walked once to find where their steps change.
+fn runs(s: &Segment) -> Vec<Segment> {
+    let (dx, dy) = sub(s.to, s.from);
+    if dx == 0 || dy == 0 || dx.abs() == dy.abs() {
+        return vec![*s];
+    }
+    let mut runs = Vec::new();
+    let mut cells = s.points();
+    let mut start = cells.next().unwrap();
+    let (mut end, mut step) = (start, None);
+    for p in cells {
+        let d = (p.0 - end.0, p.1 - end.1);
+        match step {
+            None => step = Some(d),
+            Some(s) if s == d => {}
+            Some(_) => {
+                runs.push(Segment::new(start, end));
+                (start, step) = (p, None);
+            }
+        }
+        end = p;
+    }
+    runs.push(Segment::new(start, end));
+    runs
+}
+
+// Length of the stretch covered by at least `min` of the inclusive ranges
+fn covered(ranges: &[(i128, i128)], min: usize) -> u128 {
+    let mut events: Vec<(i128, isize)> = ranges
+        .iter()
+        .flat_map(|(lo, hi)| [(*lo, 1), (hi + 1, -1)])
+        .collect();
+    events.sort_unstable();
+    let (mut depth, mut len) = (0, 0);
+    for (e, next) in events.iter().zip(events.iter().skip(1)) {
+        depth += e.1;
+        if depth as usize >= min {
+            len += (next.0 - e.0) as u128;
+        }
+    }
+    len
+}
+
+// Sweep for boxes too large for a grid. Segments are cut into runs along lines,
+// runs sharing a line are counted with a 1-D sweep over their ranges, and the
+// cells where lines cross are found by sweeping over x with the active runs and
+// fixed up afterwards, as each line counted them on its own.
+fn count_sparse(segments: &[Segment], min: usize) -> u128 {
+    let mut pieces: Vec<Segment> = segments.iter().flat_map(runs).collect();
+    pieces.sort_unstable_by_key(|r| r.from.0.min(r.to.0));
+
+    let mut lines: HashMap<Line, Vec<(i128, i128)>> = HashMap::new();
+    let mut crossings = HashSet::new();
+    let mut active: Vec<(Segment, Line)> = Vec::new();
+    for r in pieces {
+        let (line, lo, hi) = line_of(&r);
+        lines.entry(line).or_default().push((lo, hi));
+
+        let x = r.from.0.min(r.to.0);
+        active.retain(|(a, _)| a.from.0.max(a.to.0) >= x);
+        for (a, other) in &active {
+            if *other != line {
+                if let Some(Intersection::Point(x, y)) = r.intersection(a) {
+                    crossings.insert((x, y));
+                }
+            }
+        }
+        active.push((r, line));
+    }
+
+    let mut total: u128 = lines.values().map(|ranges| covered(ranges, min)).sum();
+    for c in crossings {
+        let depths = lines_through(c).map(|(line, t)| {
+            lines.get(&line).map_or(0, |ranges| {
+                ranges.iter().filter(|r| (r.0..=r.1).contains(&t)).count()
+            })
+        });
+        let counted = depths.iter().filter(|d| **d >= min).count() as u128;
+        let covers = (depths.iter().sum::<usize>() >= min) as u128;
+        total = total - counted + covers;
+    }
+    total
+}
+
+// Number of cells covered by at least `min` (and at least one) of the rasterised
+// segments. Counted on a dense grid spanning their bounding box when that is
+// small enough, with a sweep otherwise. Saturates at usize::MAX.
+pub fn count_overlaps(segments: &[Segment], min: usize) -> usize {
+    let min = min.max(1);
+    let xs = segments.iter().flat_map(|s| [s.from.0, s.to.0]);
+    let ys = segments.iter().flat_map(|s| [s.from.1, s.to.1]);
+    let (x0, x1) = match (xs.clone().min(), xs.max()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return 0,
+    };
+    let (y0, y1) = (ys.clone().min().unwrap(), ys.max().unwrap());
+    let span = |lo: isize, hi: isize| (hi as i128 - lo as i128 + 1) as u128;
+
+    match span(x0, x1).checked_mul(span(y0, y1)) {
+        Some(area) if area <= DENSE_LIMIT => {}
+        _ => {
+            let n = count_sparse(segments, min);
+            return usize::try_from(n).unwrap_or(usize::MAX);
+        }
+    }
+
+    let w = span(x0, x1) as usize;
+    let mut counts = vec![0u32; w * (y1 - y0 + 1) as usize];
+    for s in segments {
+        for (x, y) in s.points() {
+            counts[(y - y0) as usize * w + (x - x0) as usize] += 1;
+        }
+    }
+    counts.iter().filter(|c| **c as usize >= min).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::raster::{count_overlaps, Intersection, Segment};
+
+    #[test]
+    fn test_points() {
+        let s = Segment::new((0, 0), (5, 2));
+        let points: Vec<_> = s.points().collect();
+        assert_eq!(points, [(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (5, 2)]);
+        assert_eq!(Segment::new((3, 3), (1, 1)).points().count(), 3);
+        assert_eq!(Segment::new((2, 2), (2, 2)).points().count(), 1);
+        assert_eq!(
+            Segment::parse("0,9 -> 5,9"),
+            Ok(Segment::new((0, 9), (5, 9)))
+        );
+        assert!(Segment::parse("0,9 -> 5").is_err());
+
+        let wide = Segment::new((isize::MIN, 0), (isize::MAX, 1));
+        let start: Vec<_> = wide.points().take(2).collect();
+        assert_eq!(start, [(isize::MIN, 0), (isize::MIN + 1, 0)]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a = Segment::new((0, 0), (4, 4));
+        assert_eq!(
+            a.intersection(&Segment::new((0, 4), (4, 0))),
+            Some(Intersection::Point(2, 2))
+        );
+        // Crosses at (1.5, 1.5)
+        assert_eq!(a.intersection(&Segment::new((0, 3), (3, 0))), None);
+        assert_eq!(a.intersection(&Segment::new((1, 0), (5, 4))), None);
+        assert_eq!(
+            a.intersection(&Segment::new((6, 6), (2, 2))),
+            Some(Intersection::Overlap(Segment::new((2, 2), (4, 4))))
+        );
+        assert_eq!(
+            a.intersection(&Segment::new((4, 4), (9, 9))),
+            Some(Intersection::Point(4, 4))
+        );
+
+        let dot = Segment::new((0, 0), (0, 0));
+        let b = Segment::new((5, 5), (9, 9));
+        assert_eq!(dot.intersection(&b), None);
+        assert_eq!(b.intersection(&dot), None);
+        assert_eq!(dot.intersection(&a), Some(Intersection::Point(0, 0)));
+        assert_eq!(a.intersection(&dot), Some(Intersection::Point(0, 0)));
+        assert_eq!(dot.intersection(&dot), Some(Intersection::Point(0, 0)));
+        assert_eq!(dot.intersection(&Segment::new((1, 1), (1, 1))), None);
+    }
+
+    #[test]
+    fn test_count_overlaps() {
+        let segments = [
+            Segment::new((0, 0), (4, 0)),
+            Segment::new((2, -2), (2, 2)),
+            Segment::new((0, 0), (6, 3)),
+        ];
+        // Shared cells are (0, 0), (2, 0) and (2, 1)
+        assert_eq!(count_overlaps(&segments, 2), 3);
+        assert_eq!(count_overlaps(&segments, 1), 5 + 5 + 7 - 3);
+        assert_eq!(count_overlaps(&[], 1), 0);
+
+        // Far apart, too large a box for a dense grid
+        let far = [
+            Segment::new((0, 0), (3, 0)),
+            Segment::new((2, 0), (2, 1)),
+            Segment::new((isize::MAX - 1, isize::MIN), (isize::MAX, isize::MIN)),
+        ];
+        assert_eq!(count_overlaps(&far, 2), 1);
+        assert_eq!(count_overlaps(&far, 1), 4 + 1 + 2);
+
+        // Long lines through the origin, with a short stretch shared along the row
+        let n = 1_000_000_000_000;
+        let long = [
+            Segment::new((-n, 0), (n, 0)),
+            Segment::new((0, n), (0, -n)),
+            Segment::new((5, 5), (-5, -5)),
+            Segment::new((10, 0), (20, 0)),
+        ];
+        assert_eq!(count_overlaps(&long, 3), 1);
+        assert_eq!(count_overlaps(&long, 2), 1 + 11);
+        assert_eq!(count_overlaps(&long, 1), (2 * n + 1) as usize * 2 - 1 + 10);
+
+        // The sweep agrees with the grid on any slope, a far away cell forces it
+        let mixed = [
+            Segment::new((0, 0), (9, 4)),
+            Segment::new((9, 0), (0, 7)),
+            Segment::new((2, 0), (5, 9)),
+            Segment::new((0, 3), (9, 3)),
+            Segment::new((4, 4), (4, 4)),
+            Segment::new((1, 8), (8, 1)),
+            Segment::new((0, 1), (7, 2)),
+            Segment::new((7, 2), (7, 2)),
+        ];
+        let mut sparse = mixed.to_vec();
+        sparse.push(Segment::new(
+            (isize::MAX, isize::MAX),
+            (isize::MAX, isize::MAX),
+        ));
+        for min in 1..5 {
+            let extra = (min == 1) as usize;
+            assert_eq!(
+                count_overlaps(&sparse, min),
+                count_overlaps(&mixed, min) + extra
+            );
+        }
+        assert!(count_overlaps(&mixed, 3) > 0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+pub struct SearchPath<S> {
+    pub cost: usize,
+    pub states: Vec<S>, // from start to goal, both included
+}
+
+// Dijkstra over an implicit state graph. States are interned so the heap only
+// holds (cost, id) pairs and each state is cloned once.
+pub fn least_cost_path<S, I, N, G>(
+    start: S,
+    mut successors: N,
+    mut is_goal: G,
+) -> Option<SearchPath<S>>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, usize)>,
+    G: FnMut(&S) -> bool,
+{
+    let mut states: Vec<S> = vec![start.clone()];
+    let mut ids: HashMap<S, usize> = HashMap::from([(start, 0)]);
+    let mut dist: Vec<usize> = vec![0];
+    let mut prev: Vec<Option<usize>> = vec![None];
+    let mut heap = BinaryHeap::from([Reverse((0, 0))]);
+
+    while let Some(Reverse((cost, u))) = heap.pop() {
+        if cost > dist[u] {
+            continue;
+        }
+
+        if is_goal(&states[u]) {
+            let mut path = vec![u];
+            while let Some(p) = prev[*path.last().unwrap()] {
+                path.push(p);
+            }
+            return Some(SearchPath {
+                cost,
+                states: path.into_iter().rev().map(|i| states[i].clone()).collect(),
+            });
+        }
+
+        for (next, step_cost) in successors(&states[u]) {
+            let next_cost = cost + step_cost;
+            let v = match ids.get(&next) {
+                Some(&v) => v,
+                None => {
+                    states.push(next.clone());
+                    ids.insert(next, states.len() - 1);
+                    dist.push(usize::MAX);
+                    prev.push(None);
+                    states.len() - 1
+                }
+            };
+
+            if next_cost < dist[v] {
+                dist[v] = next_cost;
+                prev[v] = Some(u);
+                heap.push(Reverse((next_cost, v)));
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::ops::{Add, Neg, Sub};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Point3 {
+    pub fn new(x: isize, y: isize, z: isize) -> Point3 {
+        Point3 { x, y, z }
+    }
+
+    pub fn parse(s: &str) -> Result<Point3, String> {
+        let c: Vec<isize> = s
+            .split(',')
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| format!("invalid point \"{}\"", s))
+            })
+            .collect::<Result<_, _>>()?;
+        match c[..] {
+            [x, y, z] => Ok(Point3 { x, y, z }),
+            _ => Err(format!("expected 3 coordinates in \"{}\"", s)),
+        }
+    }
+
+    pub fn manhattan(&self, other: &Point3) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+
+    pub fn dist_sq(&self, other: &Point3) -> isize {
+        let d = *self - *other;
+        d.x * d.x + d.y * d.y + d.z * d.z
+    }
+
+    fn to_array(self) -> [isize; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl Add for Point3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+// Proper rotation as a signed permutation matrix (determinant 1)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rotation {
+    pub m: [[isize; 3]; 3],
+}
+
+impl Rotation {
+    pub fn identity() -> Rotation {
+        Rotation {
+            m: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        }
+    }
+
+    pub fn apply(&self, p: &Point3) -> Point3 {
+        let v = p.to_array();
+        let r = self
+            .m
+            .map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2]);
+        Point3::new(r[0], r[1], r[2])
+    }
+
+    // self after other, i.e. (self * other).apply(p) == self.apply(other.apply(p))
+    pub fn compose(&self, other: &Rotation) -> Rotation {
+        let mut m = [[0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..3).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Rotation { m }
+    }
+
+    // Rotation matrices are orthogonal, so the inverse is the transpose
+    pub fn inverse(&self) -> Rotation {
+        let mut m = [[0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Rotation { m }
+    }
+
+    pub fn determinant(&self) -> isize {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+const AXIS_PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+// All 24 orientations of a cube: signed axis permutations with determinant 1
+pub fn rotations() -> Vec<Rotation> {
+    AXIS_PERMUTATIONS
+        .iter()
+        .flat_map(|perm| {
+            (0..8).map(move |signs| {
+                let mut m = [[0; 3]; 3];
+                for (row, &axis) in perm.iter().enumerate() {
+                    m[row][axis] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+                Rotation { m }
+            })
+        })
+        .filter(|r| r.determinant() == 1)
+        .collect()
+}
+
+// Rigid transform mapping a point cloud into the reference frame: p -> r(p) + t
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub translation: Point3,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            rotation: Rotation::identity(),
+            translation: Point3::default(),
+        }
+    }
+
+    pub fn apply(&self, p: &Point3) -> Point3 {
+        self.rotation.apply(p) + self.translation
+    }
+
+    // self after other
+    pub fn compose(&self, other: &Transform) -> Transform {
+        Transform {
+            rotation: self.rotation.compose(&other.rotation),
+            translation: self.apply(&other.translation),
+        }
+    }
+}
+
+// Multiset of pairwise squared distances, invariant under rotation and translation
+pub fn fingerprint(cloud: &[Point3]) -> HashMap<isize, usize> {
+    let mut fp = HashMap::new();
+    for (i, a) in cloud.iter().enumerate() {
+        for b in &cloud[i + 1..] {
+            *fp.entry(a.dist_sq(b)).or_insert(0) += 1;
+        }
+    }
+    fp
+}
+
+pub fn fingerprint_overlap(a: &HashMap<isize, usize>, b: &HashMap<isize, usize>) -> usize {
+    a.iter()
+        .map(|(d, n)| (*n).min(*b.get(d).unwrap_or(&0)))
+        .sum()
+}
+
+// Finds the transform that maps at least `min_matches` points of `cloud` onto points
+// of `reference`. Clouds whose distance fingerprints cannot share that many points
+// are rejected without trying any rotations.
+pub fn align(reference: &[Point3], cloud: &[Point3], min_matches: usize) -> Option<Transform> {
+    let min_pairs = min_matches * min_matches.saturating_sub(1) / 2;
+    if fingerprint_overlap(&fingerprint(reference), &fingerprint(cloud)) < min_pairs {
+        return None;
+    }
+
+    for rotation in rotations() {
+        let rotated: Vec<Point3> = cloud.iter().map(|p| rotation.apply(p)).collect();
+        let mut votes: HashMap<Point3, usize> = HashMap::new();
+        for r in reference {
+            for p in &rotated {
+                let v = votes.entry(*r - *p).or_insert(0);
+                *v += 1;
+                if *v >= min_matches {
+                    return Some(Transform {
+                        rotation,
+                        translation: *r - *p,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lib::space::{align, rotations, Point3, Rotation, Transform};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_rotations() {
+        let rs = rotations();
+        assert_eq!(rs.len(), 24);
+        assert_eq!(rs.iter().collect::<HashSet<_>>().len(), 24);
+        for a in &rs {
+            assert_eq!(a.compose(&a.inverse()), Rotation::identity());
+            for b in &rs {
+                assert!(rs.contains(&a.compose(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_align() {
+        let reference: Vec<Point3> = (0..15)
+            .map(|i| Point3::new(i * 7 % 11, i * i % 13 - 6, i * 3 - 20))
+            .collect();
+        let t = Transform {
+            rotation: rotations()[17],
+            translation: Point3::new(40, -3, 12),
+        };
+        let cloud: Vec<Point3> = reference.iter().map(|p| t.apply(p)).collect();
+
+        let found = align(&reference, &cloud, 12).unwrap();
+        for (r, c) in reference.iter().zip(cloud.iter()) {
+            assert_eq!(found.apply(c), *r);
+        }
+        assert_eq!(align(&reference, &cloud[..10], 12), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::lib;
+use crate::lib::counter::{Counter, Tie};
 
 fn string_to_bin_vec(s: &String) -> Vec<bool> {
     s.chars()
@@ -9,33 +10,40 @@ fn string_to_bin_vec(s: &String) -> Vec<bool> {
         .collect()
 }
 
-fn count_ones(mut state: Vec<usize>, current: &Vec<bool>) -> Vec<usize> {
-    if state.len() < current.len() {
-        state.resize(current.len(), 0)
-    }
+// Most common bit in each column, ties go to 1. Least common is the inverse.
+fn gamma_bits(rows: &[Vec<bool>]) -> Vec<bool> {
+    let ones: Counter<usize> = rows
+        .iter()
+        .flat_map(|r| r.iter().enumerate().filter(|(_, b)| **b).map(|(i, _)| i))
+        .collect();
+    (0..rows[0].len())
+        .map(|i| ones[&i] >= rows.len() - ones[&i])
+        .collect()
+}
 
-    for (i, b) in current.iter().enumerate() {
-        if *b {
-            state[i] += 1
+// Keeps the rows whose bit matches the column's most (or least) common one,
+// column by column, until one row is left
+fn rating(rows: &[Vec<bool>], most: bool) -> Vec<bool> {
+    let mut tmp = rows.to_vec();
+
+    for i in 0..rows[0].len() {
+        let bits: Counter<bool> = tmp.iter().map(|v| v[i]).collect();
+        let keep = match most {
+            true => bits.most_common(Tie::Largest),
+            false => bits.least_common(Tie::Smallest),
+        };
+        let keep = *keep.unwrap();
+        tmp.retain(|v| v[i] == keep);
+        if tmp.len() <= 1 {
+            break;
         }
     }
 
-    state
+    tmp.swap_remove(0)
 }
 
-fn threshold(src: &Vec<usize>, limit: usize, remainder: usize) -> Vec<bool> {
-    src.iter().fold(vec![], |mut s, v| {
-        s.push(*v >= limit + remainder);
-        s
-    })
-}
-
-fn bin_vec_to_dec(vec: &Vec<bool>) -> usize {
-    let s: String = vec
-        .iter()
-        .map(|x| char::from_digit(*x as u32, 2).expect("error"))
-        .collect();
-    usize::from_str_radix(&s, 2).unwrap_or(0)
+fn bin_vec_to_dec(vec: &[bool]) -> usize {
+    vec.iter().fold(0, |acc, b| acc << 1 | *b as usize)
 }
 
 pub fn puzzle1(input_filename: &str) -> usize {
@@ -44,10 +52,8 @@ pub fn puzzle1(input_filename: &str) -> usize {
         .map(string_to_bin_vec)
         .collect();
 
-    let ones = parsed.iter().fold(vec![], count_ones);
-
-    let thresh = threshold(&ones, parsed.len() / 2, parsed.len() % 2);
-    let thresh_inv = thresh.iter().map(|b| !*b).collect();
+    let thresh = gamma_bits(&parsed);
+    let thresh_inv: Vec<bool> = thresh.iter().map(|b| !*b).collect();
 
     let gamma = bin_vec_to_dec(&thresh);
     let epsilon = bin_vec_to_dec(&thresh_inv);
@@ -61,31 +67,8 @@ pub fn puzzle2(input_filename: &str) -> usize {
         .map(string_to_bin_vec)
         .collect();
 
-    let mut tmp = parsed.clone();
-
-    for i in 0..=parsed[0].len() {
-        let ones = tmp.iter().fold(vec![], count_ones);
-        let thresh = threshold(&ones, tmp.len() / 2, tmp.len() % 2);
-        tmp = tmp.into_iter().filter(|v| v[i] == thresh[i]).collect();
-        if tmp.len() <= 1 {
-            break;
-        }
-    }
-
-    let oxygen = bin_vec_to_dec(&tmp[0]);
-
-    let mut tmp = parsed.clone();
-
-    for i in 0..=parsed[0].len() {
-        let ones = tmp.iter().fold(vec![], count_ones);
-        let thresh = threshold(&ones, tmp.len() / 2, tmp.len() % 2);
-        tmp = tmp.into_iter().filter(|v| v[i] != thresh[i]).collect();
-        if tmp.len() <= 1 {
-            break;
-        }
-    }
-
-    let co2 = bin_vec_to_dec(&tmp[0]);
+    let oxygen = bin_vec_to_dec(&rating(&parsed, true));
+    let co2 = bin_vec_to_dec(&rating(&parsed, false));
 
     oxygen * co2
 }
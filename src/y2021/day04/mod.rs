@@ -1,174 +1,253 @@
+use std::fmt;
+
 use crate::lib;
-use std::collections::HashSet;
 
-const GRID_SIZE: usize = 5;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Col(usize),
+    Diagonal,     // top left to bottom right
+    AntiDiagonal, // top right to bottom left
+}
 
-#[derive(Debug)]
-struct Board {
-    numbers: [usize; GRID_SIZE * GRID_SIZE],
-    marked: [bool; GRID_SIZE * GRID_SIZE],
+// Square board of any size, cells stored row by row
+#[derive(Debug, Clone)]
+pub struct Board {
+    size: usize,
+    numbers: Vec<usize>,
+    marked: Vec<bool>,
 }
 
-#[derive(Debug)]
-struct Game {
+#[derive(Debug, Clone)]
+pub struct Game {
     numbers: Vec<usize>,
     boards: Vec<Board>,
-    next_number_idx: usize,
-    wins: Vec<(usize, usize, u8)>, // 1 = row, 2 = col
+    diagonals: bool,
 }
 
-fn buf_to_board_numbers(buf: &Vec<String>) -> [usize; GRID_SIZE * GRID_SIZE] {
-    let mut board_numbers = [0; GRID_SIZE * GRID_SIZE];
-    for (i, n) in buf.join(" ").split_whitespace().enumerate() {
-        board_numbers[i] = n.parse().unwrap();
-    }
-    board_numbers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Win {
+    pub board: usize,
+    pub line: Line,
+    pub number: usize,
+    pub score: usize,
 }
 
-fn parse_game(input: &Vec<String>) -> Game {
-    let game_numbers = input[0]
-        .split(",")
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect();
-
-    let mut boards: Vec<Board> = vec![];
-
-    let mut buf: Vec<String> = vec![];
+// What happened when a number was called. A board wins only once, with the
+// first line it completes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub number: usize,
+    pub marked: Vec<usize>,
+    pub won: Vec<Win>,
+}
 
-    for l in &input[1..] {
-        if l == "" {
-            continue;
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Row(i) => write!(f, "row {}", i),
+            Line::Col(i) => write!(f, "column {}", i),
+            Line::Diagonal => write!(f, "diagonal"),
+            Line::AntiDiagonal => write!(f, "anti-diagonal"),
         }
+    }
+}
 
-        buf.push(l.to_string());
+impl Line {
+    fn cells(&self, size: usize) -> Vec<usize> {
+        (0..size)
+            .map(|k| match *self {
+                Line::Row(i) => i * size + k,
+                Line::Col(i) => k * size + i,
+                Line::Diagonal => k * size + k,
+                Line::AntiDiagonal => k * size + size - 1 - k,
+            })
+            .collect()
+    }
+}
 
-        if buf.len() >= GRID_SIZE {
-            boards.push(Board {
-                numbers: buf_to_board_numbers(&buf),
-                marked: [false; GRID_SIZE * GRID_SIZE],
-            });
-            buf.clear()
+impl Board {
+    pub fn parse(lines: &[String]) -> Result<Board, String> {
+        let size = lines.len();
+        let mut numbers = Vec::with_capacity(size * size);
+        for l in lines {
+            let row = l
+                .split_whitespace()
+                .map(|n| n.parse().map_err(|_| format!("invalid number \"{}\"", n)))
+                .collect::<Result<Vec<usize>, String>>()?;
+            if row.len() != size {
+                return Err(format!(
+                    "row \"{}\" of a {} row board is not {} long",
+                    l, size, size
+                ));
+            }
+            numbers.extend(row);
         }
+        Ok(Board {
+            size,
+            marked: vec![false; numbers.len()],
+            numbers,
+        })
     }
 
-    Game {
-        numbers: game_numbers,
-        boards: boards,
-        next_number_idx: 0,
-        wins: vec![],
+    pub fn size(&self) -> usize {
+        self.size
     }
-}
 
-fn game_round(game: &mut Game, number: usize) {
-    for i in 0..game.boards.len() {
-        let board = &mut game.boards[i];
-        for j in 0..board.numbers.len() {
-            if board.numbers[j] == number {
-                board.marked[j] = true;
+    // Marks every cell with the number, true if there was one
+    pub fn mark(&mut self, number: usize) -> bool {
+        let mut found = false;
+        for (n, m) in self.numbers.iter().zip(self.marked.iter_mut()) {
+            if *n == number {
+                *m = true;
+                found = true;
             }
         }
+        found
+    }
+
+    pub fn lines(&self, diagonals: bool) -> Vec<Line> {
+        let mut lines: Vec<Line> = (0..self.size)
+            .flat_map(|i| [Line::Row(i), Line::Col(i)])
+            .collect();
+        if diagonals {
+            lines.extend([Line::Diagonal, Line::AntiDiagonal]);
+        }
+        lines
+    }
+
+    pub fn winning_line(&self, diagonals: bool) -> Option<Line> {
+        self.lines(diagonals)
+            .into_iter()
+            .find(|l| l.cells(self.size).iter().all(|c| self.marked[*c]))
+    }
+
+    pub fn unmarked_sum(&self) -> usize {
+        self.numbers
+            .iter()
+            .zip(self.marked.iter())
+            .filter(|(_, m)| !**m)
+            .map(|(n, _)| n)
+            .sum()
     }
 }
 
-fn game_check_round(game: &mut Game) -> Option<HashSet<usize>> {
-    let mut winners: HashSet<usize> = HashSet::new();
+impl Game {
+    // Called numbers on the first line, then boards separated by blank lines
+    pub fn parse(input: &[String]) -> Result<Game, String> {
+        let (first, rest) = input.split_first().ok_or("empty input")?;
+        let numbers = first
+            .split(',')
+            .map(|n| {
+                n.trim()
+                    .parse()
+                    .map_err(|_| format!("invalid number \"{}\"", n))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
 
-    for i in 0..game.boards.len() {
-        let board = &game.boards[i];
-        for j in 0..GRID_SIZE {
-            let mut row_ok = true;
-            let mut col_ok = true;
+        let boards = rest
+            .split(|l| l.trim().is_empty())
+            .filter(|b| !b.is_empty())
+            .map(Board::parse)
+            .collect::<Result<Vec<Board>, String>>()?;
 
-            for k in 0..GRID_SIZE {
-                row_ok &= board.marked[j * GRID_SIZE + k]; // j = row
-                col_ok &= board.marked[j + k * GRID_SIZE]; // j = col
-            }
+        Ok(Game {
+            numbers,
+            boards,
+            diagonals: false,
+        })
+    }
 
-            if row_ok && game.wins.iter().find(|w| w.0 == i && w.1 == j && w.2 == 1) == None {
-                game.wins.push((i, j, 1));
-                winners.insert(i);
-            }
+    pub fn with_diagonals(mut self, diagonals: bool) -> Game {
+        self.diagonals = diagonals;
+        self
+    }
 
-            if col_ok && game.wins.iter().find(|w| w.0 == i && w.1 == j && w.2 == 2) == None {
-                game.wins.push((i, j, 2));
-                winners.insert(i);
-            }
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    pub fn play(&self) -> Play<'_> {
+        Play {
+            game: self,
+            boards: self.boards.clone(),
+            won: vec![false; self.boards.len()],
+            next: 0,
         }
     }
 
-    if winners.len() > 0 {
-        return Some(winners);
+    // Every board's win in the order they happened, boards that never win are left out
+    pub fn winning_order(&self) -> Vec<Win> {
+        self.play().flat_map(|e| e.won).collect()
     }
+}
+
+// Calls the numbers one at a time
+pub struct Play<'a> {
+    game: &'a Game,
+    boards: Vec<Board>,
+    won: Vec<bool>,
+    next: usize,
+}
 
-    None
+impl Play<'_> {
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
 }
 
-impl Iterator for Game {
-    type Item = (usize, HashSet<usize>);
+impl Iterator for Play<'_> {
+    type Item = Event;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for i in self.next_number_idx..self.numbers.len() {
-            let number = self.numbers[i];
-            game_round(self, number);
-            if i >= 4 {
-                if let Some(winners) = game_check_round(self) {
-                    self.next_number_idx = i + 1;
-                    return Some((number, winners));
-                }
+        let number = *self.game.numbers.get(self.next)?;
+        self.next += 1;
+
+        let mut event = Event {
+            number,
+            marked: vec![],
+            won: vec![],
+        };
+        for (i, board) in self.boards.iter_mut().enumerate() {
+            if !board.mark(number) {
+                continue;
+            }
+            event.marked.push(i);
+            if self.won[i] {
+                continue;
+            }
+            if let Some(line) = board.winning_line(self.game.diagonals) {
+                self.won[i] = true;
+                event.won.push(Win {
+                    board: i,
+                    line,
+                    number,
+                    score: number * board.unmarked_sum(),
+                });
             }
         }
-        None
+        Some(event)
     }
 }
 
 pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    let mut game = parse_game(&input);
-
-    if let Some((number, winners)) = game.next() {
-        for i in winners.iter() {
-            let board = &game.boards[*i];
-            let sum_not_marked = board
-                .marked
-                .iter()
-                .zip(board.numbers.iter())
-                .filter(|x| !*x.0)
-                .fold(0, |v, x| v + x.1);
-            return number * sum_not_marked;
-        }
-    }
+    let game = Game::parse(&input).unwrap();
 
-    0
+    game.winning_order().first().map_or(0, |w| w.score)
 }
 
 pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    let mut game = parse_game(&input);
-
-    let mut all_winners: HashSet<usize> = HashSet::new();
-    let mut last_winner_sum: usize = 0;
-
-    while let Some((number, winners)) = game.next() {
-        for i in winners.iter() {
-            if all_winners.insert(*i) {
-                let board = &game.boards[*i];
-                let sum_not_marked = board
-                    .marked
-                    .iter()
-                    .zip(board.numbers.iter())
-                    .filter(|x| !*x.0)
-                    .fold(0, |v, x| v + x.1);
-                last_winner_sum = number * sum_not_marked;
-            }
-        }
-    }
+    let game = Game::parse(&input).unwrap();
 
-    last_winner_sum
+    game.winning_order().last().map_or(0, |w| w.score)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::y2021::day04;
+    use crate::y2021::day04::{Game, Line};
 
     #[test]
     fn test1() {
@@ -181,4 +260,50 @@ mod tests {
         assert_eq!(day04::puzzle2("src/y2021/day04/test.txt"), 1924);
         assert_eq!(day04::puzzle2("src/y2021/day04/input.txt"), 31755);
     }
+
+    #[test]
+    fn test_rules() {
+        let input: Vec<String> = [
+            "5,1,9,2,3,7",
+            "",
+            "1 2 3",
+            "4 5 6",
+            "7 8 9",
+            "",
+            "9 8",
+            "7 1",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let game = Game::parse(&input).unwrap();
+        assert_eq!(game.boards()[1].size(), 2);
+        let order = game.winning_order();
+        assert_eq!(order.len(), 2);
+        assert_eq!((order[0].board, order[0].line), (0, Line::Row(0)));
+        assert_eq!(order[0].score, 3 * (4 + 6 + 7 + 8));
+        assert_eq!((order[1].board, order[1].line), (1, Line::Col(0)));
+        assert_eq!(order[1].score, 7 * 8);
+
+        let events: Vec<_> = game.play().collect();
+        assert_eq!(events[0].marked, [0]);
+        assert_eq!(events[1].marked, [0, 1]);
+        assert!(events[1].won.is_empty());
+
+        let game = game.with_diagonals(true);
+        let first = &game.winning_order()[0];
+        assert_eq!(
+            (first.board, first.line, first.number),
+            (0, Line::Diagonal, 9)
+        );
+
+        assert!(Game::parse(&input[..4]).is_err());
+        // Four numbers on a board of two rows, but not two per row
+        let ragged: Vec<String> = ["1,2", "", "1 2 3", "4"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(Game::parse(&ragged).is_err());
+    }
 }
@@ -1,12 +1,12 @@
 use crate::lib;
+use crate::lib::matrix::Matrix;
 
-fn tick(state: [usize; 9]) -> [usize; 9] {
-    let mut next_state: [usize; 9] = [0; 9];
+// Fish counted by days left on their timer
+fn tick(state: &[u64]) -> Vec<u64> {
+    let mut next_state = vec![0; 9];
     let zeros = state[0];
 
-    for i in 0..8 {
-        next_state[i] = state[i + 1];
-    }
+    next_state[..8].copy_from_slice(&state[1..]);
 
     next_state[6] += zeros;
     next_state[8] = zeros;
@@ -14,44 +14,49 @@ fn tick(state: [usize; 9]) -> [usize; 9] {
     next_state
 }
 
-pub fn puzzle1(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let mut state = input[0].split(",").map(|s| s.parse().unwrap()).fold(
-        [0; 9],
-        |mut m: [usize; 9], v: usize| {
+fn parse_state(input: &[String]) -> Vec<u64> {
+    input[0]
+        .split(',')
+        .map(|s| s.parse::<usize>().unwrap())
+        .fold(vec![0; 9], |mut m, v| {
             m[v] += 1;
             m
-        },
-    );
+        })
+}
 
-    for _ in 0..80 {
-        state = tick(state);
-    }
+// Population after `days`, None if it no longer fits in a u64
+pub fn population(input_filename: &str, days: u64) -> Option<u64> {
+    let input = lib::read_lines(input_filename);
+    let state = parse_state(&input);
 
-    state.iter().fold(0, |mut acc: usize, c| {
-        acc += c;
-        acc
-    })
+    let step = Matrix::from_linear(9, tick).checked_pow(days)?;
+    step.checked_apply(&state)?
+        .into_iter()
+        .try_fold(0u64, |acc, c| acc.checked_add(c))
 }
 
-pub fn puzzle2(input_filename: &str) -> usize {
+pub fn population_mod(input_filename: &str, days: u64, modulus: u64) -> Result<u64, String> {
+    if modulus == 0 {
+        return Err("population modulo 0".to_string());
+    }
     let input = lib::read_lines(input_filename);
-    let mut state = input[0].split(",").map(|s| s.parse().unwrap()).fold(
-        [0; 9],
-        |mut m: [usize; 9], v: usize| {
-            m[v] += 1;
-            m
-        },
-    );
+    let state = parse_state(&input);
 
-    for _ in 0..256 {
-        state = tick(state);
-    }
+    let step = Matrix::from_linear(9, tick).pow_mod(days, modulus);
+    Ok(step
+        .apply_mod(&state, modulus)
+        .into_iter()
+        .fold(0, |acc, c| {
+            ((acc as u128 + c as u128) % modulus as u128) as u64
+        }))
+}
 
-    state.iter().fold(0, |mut acc: usize, c| {
-        acc += c;
-        acc
-    })
+pub fn puzzle1(input_filename: &str) -> usize {
+    population(input_filename, 80).unwrap() as usize
+}
+
+pub fn puzzle2(input_filename: &str) -> usize {
+    population(input_filename, 256).unwrap() as usize
 }
 
 #[cfg(test)]
@@ -69,4 +74,25 @@ mod tests {
         assert_eq!(day06::puzzle2("src/y2021/day06/test.txt"), 26984457539);
         assert_eq!(day06::puzzle2("src/y2021/day06/input.txt"), 1639643057051);
     }
+
+    #[test]
+    fn test_population() {
+        let test = "src/y2021/day06/test.txt";
+        assert_eq!(day06::population(test, 18), Some(26));
+        assert_eq!(day06::population(test, 1000), None);
+        assert_eq!(
+            day06::population_mod(test, 256, 1_000_000_007),
+            Ok(26984457539 % 1_000_000_007)
+        );
+        assert_eq!(
+            day06::population_mod(test, 1_000_000_000_000, 1_000_000_007),
+            Ok(995077479)
+        );
+        // Residues close to u64::MAX overflow a plain u64 sum
+        assert_eq!(
+            day06::population_mod(test, 1000, u64::MAX - 58),
+            Ok(16717835861489804584)
+        );
+        assert!(day06::population_mod(test, 10, 0).is_err());
+    }
 }
@@ -1,63 +1,108 @@
 use crate::lib;
 
-fn binom(n: usize, k: usize) -> usize {
-    let mut res = 1;
-    for i in 0..k {
-        res = (res * (n - i)) / (i + 1);
-    }
-    res
+// Fuel a crab burns to move a distance. Convex costs must also be non-decreasing,
+// so the total over all crabs is convex in the target position.
+pub enum FuelCost {
+    Linear,
+    Triangular,
+    Convex(Box<dyn Fn(usize) -> usize>),
 }
 
-pub fn puzzle1(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let x: Vec<usize> = input[0].split(",").map(|s| s.parse().unwrap()).collect();
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub target: usize,
+    pub cost: usize,
+}
 
-    let x_min = *x.iter().min().unwrap();
-    let x_max = *x.iter().max().unwrap();
+fn triangular(n: usize) -> usize {
+    // Halve the even factor first so only the result can overflow
+    match n % 2 {
+        0 => (n / 2).saturating_mul(n + 1),
+        _ => n.saturating_mul(n.div_ceil(2)),
+    }
+}
 
-    let mut min_err = std::usize::MAX;
+impl FuelCost {
+    pub fn fuel(&self, distance: usize) -> usize {
+        match self {
+            FuelCost::Linear => distance,
+            FuelCost::Triangular => triangular(distance),
+            FuelCost::Convex(f) => f(distance),
+        }
+    }
 
-    for t in x_min..x_max + 1 {
-        let err = x.iter().fold(0, |mut acc: usize, v| {
-            acc += (*v as i32 - t as i32).abs() as usize;
-            acc
-        });
+    pub fn total(&self, positions: &[usize], target: usize) -> usize {
+        positions.iter().fold(0, |acc, p| {
+            acc.saturating_add(self.fuel(p.abs_diff(target)))
+        })
+    }
 
-        if err < min_err {
-            min_err = err
+    fn at(&self, positions: &[usize], target: usize) -> Alignment {
+        Alignment {
+            target,
+            cost: self.total(positions, target),
         }
     }
 
-    min_err
+    // Cheapest target, the leftmost one on ties. Linear cost is minimised at the
+    // median, triangular within half a step of the mean, anything else by a
+    // ternary search on the convex total.
+    pub fn align(&self, positions: &[usize]) -> Option<Alignment> {
+        let lo = *positions.iter().min()?;
+        let hi = *positions.iter().max()?;
+
+        match self {
+            FuelCost::Linear => {
+                let mut sorted = positions.to_vec();
+                sorted.sort_unstable();
+                Some(self.at(positions, sorted[(sorted.len() - 1) / 2]))
+            }
+            FuelCost::Triangular => {
+                let sum: u128 = positions.iter().map(|p| *p as u128).sum();
+                let mean = (sum / positions.len() as u128) as usize;
+                (mean.saturating_sub(1).max(lo)..=(mean + 1).min(hi))
+                    .map(|t| self.at(positions, t))
+                    .min_by_key(|a| a.cost)
+            }
+            FuelCost::Convex(_) => {
+                let (mut lo, mut hi) = (lo, hi);
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    if self.total(positions, mid) <= self.total(positions, mid + 1) {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
+                }
+                Some(self.at(positions, lo))
+            }
+        }
+    }
 }
 
-pub fn puzzle2(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let x: Vec<usize> = input[0].split(",").map(|s| s.parse().unwrap()).collect();
-
-    let x_min = *x.iter().min().unwrap();
-    let x_max = *x.iter().max().unwrap();
+fn parse_positions(input: &[String]) -> Vec<usize> {
+    input[0].split(',').map(|s| s.parse().unwrap()).collect()
+}
 
-    let mut min_err = std::usize::MAX;
+pub fn puzzle1(input_filename: &str) -> usize {
+    let input = lib::read_lines(input_filename);
+    let x = parse_positions(&input);
 
-    for t in x_min..x_max + 1 {
-        let err = x.iter().fold(0, |mut acc: usize, v| {
-            let steps = (*v as i32 - t as i32).abs() as usize;
-            acc += binom(steps + 1, 2);
-            acc
-        });
+    FuelCost::Linear.align(&x).unwrap().cost
+}
 
-        if err < min_err {
-            min_err = err
-        }
-    }
+pub fn puzzle2(input_filename: &str) -> usize {
+    let input = lib::read_lines(input_filename);
+    let x = parse_positions(&input);
 
-    min_err
+    FuelCost::Triangular.align(&x).unwrap().cost
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day07;
+    use crate::y2021::day07::{Alignment, FuelCost};
 
     #[test]
     fn test1() {
@@ -70,4 +115,43 @@ mod tests {
         assert_eq!(day07::puzzle2("src/y2021/day07/test.txt"), 168);
         assert_eq!(day07::puzzle2("src/y2021/day07/input.txt"), 104149091);
     }
+
+    #[test]
+    fn test_align() {
+        let x = day07::parse_positions(&lib::read_lines("src/y2021/day07/test.txt"));
+        let linear = FuelCost::Linear.align(&x).unwrap();
+        assert_eq!(
+            linear,
+            Alignment {
+                target: 2,
+                cost: 37
+            }
+        );
+        let triangular = FuelCost::Triangular.align(&x).unwrap();
+        assert_eq!(
+            triangular,
+            Alignment {
+                target: 5,
+                cost: 168
+            }
+        );
+
+        let models = [
+            FuelCost::Linear,
+            FuelCost::Triangular,
+            FuelCost::Convex(Box::new(|d| d * d)),
+            FuelCost::Convex(Box::new(|d| d.saturating_sub(3))),
+        ];
+        for cost in models {
+            let brute = (0..=16).map(|t| cost.total(&x, t)).min();
+            assert_eq!(cost.align(&x).map(|a| a.cost), brute);
+        }
+
+        let far = [0, 4_000_000_000];
+        assert_eq!(
+            FuelCost::Triangular.align(&far).unwrap().cost,
+            2 * 2_000_000_000 * 2_000_000_001 / 2
+        );
+        assert_eq!(FuelCost::Linear.align(&[]), None);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::lib;
+use crate::lib::csp::Csp;
 
 // 2 = 1
 // 3 = 7
@@ -7,23 +8,132 @@ use crate::lib;
 // 6 = 0,6,9
 // 7 = 8
 
-fn vec_diff<T: PartialEq + Copy + Clone>(a: &Vec<T>, b: &Vec<T>) -> Vec<T> {
-    let mut difference: Vec<T> = vec![];
-    for i in a {
-        if !b.contains(i) {
-            difference.push(*i);
+// Segments lit for each digit on a standard seven-segment display
+const STANDARD_LAYOUT: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+// Segment sets of each digit, segment 'a' is bit 0
+#[derive(Debug, Clone)]
+pub struct Layout {
+    digits: Vec<u64>,
+}
+
+// Segments and wires are named by lowercase letters
+const LETTERS: usize = 26;
+
+// Wire indices of a pattern, each letter must name one of the first n wires and appear once
+fn wires(p: &str, n: usize) -> Result<Vec<usize>, String> {
+    let mut seen = 0u64;
+    p.bytes()
+        .map(|c| {
+            let w = c.wrapping_sub(b'a') as usize;
+            if w >= n {
+                return Err(format!(
+                    "pattern \"{}\" uses an unknown wire, expected a to {}",
+                    p,
+                    (b'a' + n as u8 - 1) as char
+                ));
+            }
+            if seen & 1 << w != 0 {
+                return Err(format!("pattern \"{}\" repeats a wire", p));
+            }
+            seen |= 1 << w;
+            Ok(w)
+        })
+        .collect()
+}
+
+impl Layout {
+    pub fn new(digits: &[&str]) -> Result<Layout, String> {
+        let digits = digits
+            .iter()
+            .map(|d| Ok(wires(d, LETTERS)?.iter().fold(0, |m, w| m | 1 << w)))
+            .collect::<Result<Vec<u64>, String>>()?;
+        if digits.iter().all(|d| *d == 0) {
+            return Err("layout lights no segments".to_string());
         }
+        Ok(Layout { digits })
+    }
+
+    pub fn standard() -> Layout {
+        Layout::new(&STANDARD_LAYOUT).unwrap()
+    }
+
+    fn segments(&self) -> usize {
+        let lit = self.digits.iter().fold(0, |m, d| m | d);
+        (u64::BITS - lit.leading_zeros()) as usize
+    }
+
+    fn digit(&self, segments: u64) -> Option<usize> {
+        self.digits.iter().position(|d| *d == segments)
+    }
+
+    // Output digits shown through the wiring, as one number
+    fn read(&self, output: &[Vec<usize>], wiring: &[usize]) -> Result<usize, String> {
+        output.iter().try_fold(0, |acc, p| {
+            let segments = p.iter().fold(0, |m, w| m | 1 << wiring[*w]);
+            let digit = self.digit(segments).ok_or("output shows no digit")?;
+            Ok(acc * 10 + digit)
+        })
     }
-    difference
-}
 
-fn mapping<T: PartialEq + Ord + Copy + Clone>(s: &Vec<T>, decoded: &[Vec<T>; 10]) -> Option<usize> {
-    for (i, t) in decoded.iter().enumerate() {
-        if *s == *t {
-            return Some(i);
+    // Finds which wire drives which segment from the scrambled patterns and reads
+    // the output. Wiring that no or more than one reading of the output fits is an error.
+    pub fn decode(&self, patterns: &[&str], output: &[&str]) -> Result<usize, String> {
+        let n = self.segments();
+        let scopes = patterns
+            .iter()
+            .map(|p| wires(p, n))
+            .collect::<Result<Vec<_>, String>>()?;
+        let output = output
+            .iter()
+            .map(|p| wires(p, n))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut csp = Csp::new();
+        let vars: Vec<usize> = (0..n).map(|_| csp.add_var(0..n)).collect();
+        csp.all_different(vars);
+
+        for scope in scopes.iter().chain(output.iter()) {
+            let digits = &self.digits;
+            csp.constrain(scope.clone(), move |segments| {
+                let lit = segments.iter().flatten().fold(0u64, |m, s| m | 1 << s);
+                digits
+                    .iter()
+                    .any(|d| d.count_ones() as usize == segments.len() && d & lit == lit)
+            });
+        }
+
+        let first = match csp.solutions(1).pop() {
+            Some(wiring) => self.read(&output, &wiring)?,
+            None => return Err(format!("contradictory display: {}", patterns.join(" "))),
+        };
+
+        // Many wirings can agree on the output, rather than listing them all look
+        // for one that reads differently
+        let mut used: Vec<usize> = output.iter().flatten().copied().collect();
+        used.sort_unstable();
+        used.dedup();
+        let output = &output;
+        csp.constrain(used.clone(), move |segments| {
+            if segments.contains(&None) {
+                return true;
+            }
+            let mut wiring = vec![0; n];
+            for (w, s) in used.iter().zip(segments.iter().flatten()) {
+                wiring[*w] = *s;
+            }
+            self.read(output, &wiring) != Ok(first)
+        });
+        match csp.solutions(1).pop() {
+            None => Ok(first),
+            Some(wiring) => Err(format!(
+                "ambiguous display, could read {:?}",
+                [first, self.read(output, &wiring)?]
+            )),
         }
     }
-    None
 }
 
 pub fn puzzle1(input_filename: &str) -> usize {
@@ -42,128 +152,17 @@ pub fn puzzle1(input_filename: &str) -> usize {
 
 pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
+    let layout = Layout::standard();
 
-    let mut sum: usize = 0;
-
-    for l in input.iter() {
-        let ss: Vec<(Vec<char>, Vec<usize>)> = l
-            .split_whitespace()
-            .filter(|s| *s != "|")
-            .map(|s| {
-                let mut v: Vec<char> = s.chars().collect();
-                v.sort();
-                v
-            })
-            .map(|v| match v.len() {
-                2 => (v, vec![1]),
-                3 => (v, vec![7]),
-                4 => (v, vec![4]),
-                5 => (v, vec![2, 3, 5]),
-                6 => (v, vec![0, 6, 9]),
-                7 => (v, vec![8]),
-                _ => (v, vec![]),
-            })
-            .collect();
-
-        let one: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&1))
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let four: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&4))
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let seven: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&7))
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let eight: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&8))
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let six: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&6) && vec_diff(&one, &x.0).len() == 1)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let nine: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&9) && vec_diff(&x.0, &four).len() == 2)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let zero: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&0) && x.0 != nine && x.0 != six)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let five: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&5) && vec_diff(&six, &x.0).len() == 1)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let three: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&3) && vec_diff(&one, &x.0).len() == 0)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let two: Vec<char> = ss
-            .clone()
-            .into_iter()
-            .filter(|x| x.1.contains(&2) && x.0 != three && x.0 != five)
-            .map(|x| x.0)
-            .last()
-            .unwrap();
-
-        let decoded = [zero, one, two, three, four, five, six, seven, eight, nine];
-
-        let output: Vec<usize> = l
-            .split(" | ")
-            .last()
-            .unwrap()
-            .split_whitespace()
-            .map(|s| {
-                let mut v: Vec<char> = s.chars().collect();
-                v.sort();
-                v
-            })
-            .filter_map(|s| mapping(&s, &decoded))
-            .collect();
-
-        sum += output[0] * 1000 + output[1] * 100 + output[2] * 10 + output[3];
-    }
-
-    sum
+    input
+        .iter()
+        .map(|l| {
+            let (patterns, output) = l.split_once(" | ").unwrap();
+            let patterns: Vec<&str> = patterns.split_whitespace().collect();
+            let output: Vec<&str> = output.split_whitespace().collect();
+            layout.decode(&patterns, &output).unwrap()
+        })
+        .sum()
 }
 
 #[cfg(test)]
@@ -181,4 +180,44 @@ mod tests {
         assert_eq!(day08::puzzle2("src/y2021/day08/test.txt"), 61229);
         assert_eq!(day08::puzzle2("src/y2021/day08/input.txt"), 1096964);
     }
+
+    #[test]
+    fn test_layouts() {
+        let standard = day08::Layout::standard();
+        let patterns = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab";
+        let patterns: Vec<&str> = patterns.split_whitespace().collect();
+        assert_eq!(
+            standard.decode(&patterns, &["cdfeb", "fcadb", "cdfeb", "cdbaf"]),
+            Ok(5353)
+        );
+
+        // Only 1 and 7 seen, the remaining wires could be swapped freely
+        assert!(standard.decode(&["ab", "dab"], &["cefg"]).is_err());
+        assert!(standard.decode(&["ab", "dab"], &["ab"]).is_ok());
+        assert!(standard.decode(&["abc", "dab"], &["ab"]).is_err());
+
+        // Three-segment "display" where digits light a, ab and abc
+        let bars = day08::Layout::new(&["a", "ab", "abc"]).unwrap();
+        assert_eq!(bars.decode(&["c", "bc", "abc"], &["bc", "c"]), Ok(10));
+
+        assert!(bars.decode(&["c", "bc", "abc"], &["bc", "A"]).is_err());
+        assert!(bars.decode(&["c", "bc", "abc"], &["bc", "d"]).is_err());
+        assert!(bars.decode(&["c", "bb", "abc"], &["bc", "c"]).is_err());
+        assert!(standard.decode(&["abcdefg"], &["aab"]).is_err());
+        assert!(day08::Layout::new(&[]).is_err());
+        assert!(day08::Layout::new(&["", ""]).is_err());
+        assert!(day08::Layout::new(&["a_"]).is_err());
+        assert!(day08::Layout::new(&["a{"]).is_err());
+        assert!(standard.decode(&["abcdefgh"], &["ab"]).is_err());
+
+        // Twenty single-bar digits can be wired in 20! ways, decoding stops as soon
+        // as a second reading turns up
+        let letters: Vec<String> = (b'a'..b'u').map(|c| (c as char).to_string()).collect();
+        let bars: Vec<&str> = letters.iter().map(|s| s.as_str()).collect();
+        let many = day08::Layout::new(&bars).unwrap();
+        assert!(many
+            .decode(&[], &["a"])
+            .unwrap_err()
+            .starts_with("ambiguous"));
+    }
 }
@@ -1,23 +1,5 @@
 use crate::lib;
-use std::collections::HashSet;
-
-fn depth_first_search(values: &Vec<u8>, shape: &lib::Shape, i: usize) -> HashSet<usize> {
-    let mut discovered: HashSet<usize> = HashSet::new();
-    let mut stack: Vec<usize> = vec![];
-    stack.push(i);
-    while !stack.is_empty() {
-        let v = stack.pop().unwrap();
-        if discovered.insert(v) {
-            for n in lib::neighbours(shape, v, false)
-                .into_iter()
-                .filter(|j| values[*j] != 9)
-            {
-                stack.push(n);
-            }
-        }
-    }
-    discovered
-}
+use crate::lib::neighbourhood::{self, Lattice};
 
 pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
@@ -35,7 +17,6 @@ pub fn puzzle1(input_filename: &str) -> usize {
         .enumerate()
         .filter(|(i, v)| {
             lib::neighbours(&shape, *i, false)
-                .into_iter()
                 .map(|j| values[j])
                 .fold(true, |acc, cur| acc && *v < &cur)
         })
@@ -59,16 +40,21 @@ pub fn puzzle2(input_filename: &str) -> usize {
         .enumerate()
         .filter(|(i, v)| {
             lib::neighbours(&shape, *i, false)
-                .into_iter()
                 .map(|j| values[j])
                 .fold(true, |acc, cur| acc && *v < &cur)
         })
         .map(|(i, _)| i)
         .collect();
 
+    let heights = Lattice::von_neumann([shape.w, shape.h]);
     let mut basin_sizes: Vec<usize> = low_points
         .iter()
-        .map(|i| depth_first_search(&values, &shape, *i).len())
+        .map(|i| {
+            neighbourhood::bfs(&heights, *i, |j| values[j] != 9)
+                .iter()
+                .filter(|d| d.is_some())
+                .count()
+        })
         .collect();
 
     basin_sizes.sort();
@@ -1,62 +1,156 @@
 use crate::lib;
 
-const LEGAL_OPEN: [char; 4] = ['(', '[', '{', '<'];
-const LEGAL_CLOSE: [char; 4] = [')', ']', '}', '>'];
-const ERR_SCORE_CORRUPT: [usize; 4] = [3, 57, 1197, 25137];
-const ERR_SCORE_INCOMPLETE: [usize; 4] = [1, 2, 3, 4];
-
-fn line_error_score(l: &String) -> Option<(Option<usize>, Option<usize>)> {
-    let mut stack: Vec<char> = vec![];
-
-    for c in l.chars() {
-        if LEGAL_OPEN.contains(&c) {
-            // Got an opening char
-            stack.push(c);
-        } else if LEGAL_CLOSE.contains(&c) {
-            // Got a closing char
-            let o = stack.last().unwrap();
-
-            if (c as i32 - *o as i32).abs() <= 2 {
-                // Legal close
-                stack.pop();
-            } else {
-                // Incorrect close => corrupted
-                let err_idx = LEGAL_CLOSE.iter().position(|p| p == &c).unwrap();
-                let corruption_score = ERR_SCORE_CORRUPT[err_idx];
-                return Some((Some(corruption_score), None));
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pair {
+    pub open: char,
+    pub close: char,
+    pub corrupt_score: usize,
+    pub complete_score: usize,
+}
+
+const STANDARD_PAIRS: [Pair; 4] = [
+    Pair {
+        open: '(',
+        close: ')',
+        corrupt_score: 3,
+        complete_score: 1,
+    },
+    Pair {
+        open: '[',
+        close: ']',
+        corrupt_score: 57,
+        complete_score: 2,
+    },
+    Pair {
+        open: '{',
+        close: '}',
+        corrupt_score: 1197,
+        complete_score: 3,
+    },
+    Pair {
+        open: '<',
+        close: '>',
+        corrupt_score: 25137,
+        complete_score: 4,
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Valid,
+    // Positions are char indices into the line
+    Corrupted {
+        position: usize,
+        expected: char,
+        found: char,
+    },
+    Unmatched {
+        position: usize,
+        found: char,
+    },
+    Incomplete {
+        completion: String,
+    },
+}
+
+// Characters that are not part of any pair are ignored
+#[derive(Debug, Clone)]
+pub struct Checker {
+    pairs: Vec<Pair>,
+}
+
+impl Checker {
+    pub fn new(pairs: &[Pair]) -> Result<Checker, String> {
+        let mut seen = vec![];
+        for p in pairs {
+            if p.open == p.close || seen.contains(&p.open) || seen.contains(&p.close) {
+                return Err(format!("ambiguous pair {}{}", p.open, p.close));
+            }
+            seen.extend([p.open, p.close]);
+        }
+        Ok(Checker {
+            pairs: pairs.to_vec(),
+        })
+    }
+
+    pub fn standard() -> Checker {
+        Checker::new(&STANDARD_PAIRS).unwrap()
+    }
+
+    fn opened_by(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|p| p.open == c)
+    }
+
+    fn closed_by(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|p| p.close == c)
+    }
+
+    pub fn check(&self, line: &str) -> Status {
+        let mut stack: Vec<&Pair> = vec![];
+
+        for (position, c) in line.chars().enumerate() {
+            if let Some(pair) = self.opened_by(c) {
+                stack.push(pair);
+            } else if self.closed_by(c).is_some() {
+                match stack.pop() {
+                    Some(open) if open.close == c => {}
+                    Some(open) => {
+                        return Status::Corrupted {
+                            position,
+                            expected: open.close,
+                            found: c,
+                        }
+                    }
+                    None => return Status::Unmatched { position, found: c },
+                }
             }
         }
+
+        match stack.is_empty() {
+            true => Status::Valid,
+            false => Status::Incomplete {
+                completion: stack.iter().rev().map(|p| p.close).collect(),
+            },
+        }
     }
 
-    if stack.len() > 0 {
-        // Stack is not empty => incomplete
-        stack.reverse();
-        let incomplete_score = stack.iter().fold(0, |sum, c| {
-            let err_idx = LEGAL_OPEN.iter().position(|p| p == c).unwrap();
-            sum * 5 + ERR_SCORE_INCOMPLETE[err_idx]
-        });
-        return Some((None, Some(incomplete_score)));
+    // Score of the offending closer, a stray closer counts the same as a wrong one
+    pub fn corruption_score(&self, status: &Status) -> Option<usize> {
+        match status {
+            Status::Corrupted { found, .. } | Status::Unmatched { found, .. } => {
+                self.closed_by(*found).map(|p| p.corrupt_score)
+            }
+            _ => None,
+        }
     }
 
-    None
+    pub fn completion_score(&self, completion: &str) -> usize {
+        completion.chars().fold(0, |sum, c| {
+            sum * 5 + self.closed_by(c).map_or(0, |p| p.complete_score)
+        })
+    }
 }
+
 pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
+    let checker = Checker::standard();
 
     input
         .iter()
-        .filter_map(line_error_score)
-        .filter_map(|l| l.0)
-        .fold(0, |sum, e| sum + e)
+        .filter_map(|l| checker.corruption_score(&checker.check(l)))
+        .sum()
 }
 
 pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
+    let checker = Checker::standard();
 
     let mut scores: Vec<usize> = input
         .iter()
-        .filter_map(line_error_score)
-        .filter_map(|l| l.1)
+        .filter_map(|l| match checker.check(l) {
+            Status::Incomplete { completion } => Some(checker.completion_score(&completion)),
+            _ => None,
+        })
         .collect();
 
     scores.sort();
@@ -66,6 +160,7 @@ pub fn puzzle2(input_filename: &str) -> usize {
 #[cfg(test)]
 mod tests {
     use crate::y2021::day10;
+    use crate::y2021::day10::{Checker, Pair, Status};
 
     #[test]
     fn test1() {
@@ -78,4 +173,50 @@ mod tests {
         assert_eq!(day10::puzzle2("src/y2021/day10/test.txt"), 288957);
         assert_eq!(day10::puzzle2("src/y2021/day10/input.txt"), 4001832844);
     }
+
+    #[test]
+    fn test_check() {
+        let checker = Checker::standard();
+        assert_eq!(checker.check("[<>({}){}[([])<>]]"), Status::Valid);
+        assert_eq!(
+            checker.check("{([(<{}[<>[]}>{[]{[(<()>"),
+            Status::Corrupted {
+                position: 12,
+                expected: ']',
+                found: '}'
+            }
+        );
+        assert_eq!(
+            checker.check("()>"),
+            Status::Unmatched {
+                position: 2,
+                found: '>'
+            }
+        );
+        assert_eq!(
+            checker.check("[({(<(())[]>[[{[]{<()<>>"),
+            Status::Incomplete {
+                completion: "}}]])})]".to_string()
+            }
+        );
+        assert_eq!(checker.completion_score("}}]])})]"), 288957);
+
+        let quotes = Pair {
+            open: '«',
+            close: '»',
+            corrupt_score: 7,
+            complete_score: 9,
+        };
+        let checker = Checker::new(&[quotes]).unwrap();
+        let status = checker.check("«a«b»");
+        assert_eq!(checker.completion_score("»»"), 9 * 5 + 9);
+        assert_eq!(
+            status,
+            Status::Incomplete {
+                completion: "»".to_string()
+            }
+        );
+        assert_eq!(checker.corruption_score(&checker.check("»")), Some(7));
+        assert!(Checker::new(&[quotes, quotes]).is_err());
+    }
 }
@@ -1,86 +1,182 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 use crate::lib;
+use crate::lib::graph::Graph;
+
+const START: &str = "start";
+const END: &str = "end";
+
+// Caves interned to ids, small caves get a bit in the visited mask
+#[derive(Debug)]
+pub struct CaveGraph {
+    names: Vec<String>,
+    small: Vec<bool>,
+    adj: Vec<Vec<usize>>,
+    start: usize,
+    end: usize,
+}
+
+impl CaveGraph {
+    pub fn parse(input: &[String]) -> Result<CaveGraph, String> {
+        let edges = Graph::parse_edges(input)?;
+        if edges.is_directed() {
+            return Err("cave passages are undirected".to_string());
+        }
+        if edges.len() > u64::BITS as usize {
+            return Err(format!("too many caves: {}", edges.len()));
+        }
+
+        let names: Vec<String> = (0..edges.len()).map(|i| edges.node(i).clone()).collect();
+        let small: Vec<bool> = names.iter().map(|n| n.to_lowercase() == *n).collect();
+        let adj: Vec<Vec<usize>> = (0..edges.len())
+            .map(|i| edges.neighbours(i).map(|(n, _)| n).collect())
+            .collect();
+        // Paths could bounce between two big caves forever
+        for (a, next) in adj.iter().enumerate() {
+            if let Some(b) = next.iter().find(|b| !small[a] && !small[**b]) {
+                return Err(format!(
+                    "big caves {} and {} are connected",
+                    names[a], names[*b]
+                ));
+            }
+        }
+        Ok(CaveGraph {
+            small,
+            adj,
+            start: edges.id(&START.to_string()).ok_or("no start cave")?,
+            end: edges.id(&END.to_string()).ok_or("no end cave")?,
+            names,
+        })
+    }
 
-type Edge = (String, String);
-
-fn path_count(
-    node: &String,
-    graph: &Vec<Edge>,
-    avoid: HashSet<String>,
-    skipped: bool,
-    path: Vec<String>,
-    paths: &mut HashSet<String>,
-) -> usize {
-    let mut path = path;
-    path.push(node.clone());
-
-    if node == "end" {
-        let r = path.join(",");
-        paths.insert(r) as usize
-    } else {
-        let mut alt_paths = 0;
-
-        let initial_neighbours = graph
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn is_small(&self, id: usize) -> bool {
+        self.small[id]
+    }
+
+    // Number of paths from start to end that enter small caves they have already
+    // visited at most `revisits` times in total, whether that is one cave entered
+    // repeatedly or several caves twice. Memoized on (cave, visited small caves,
+    // revisits left).
+    pub fn count_paths(&self, revisits: usize) -> usize {
+        let mut memo = HashMap::new();
+        self.count_from(self.start, 1 << self.start, revisits, &mut memo)
+    }
+
+    fn count_from(
+        &self,
+        node: usize,
+        visited: u64,
+        revisits: usize,
+        memo: &mut HashMap<(usize, u64, usize), usize>,
+    ) -> usize {
+        if node == self.end {
+            return 1;
+        }
+        if let Some(&n) = memo.get(&(node, visited, revisits)) {
+            return n;
+        }
+
+        let count = self.adj[node]
             .iter()
-            .filter(|e| e.0 == *node || e.1 == *node)
-            .map(|e| if e.0 == *node { &e.1 } else { &e.0 });
-
-        let mut avoid = avoid.clone();
-
-        // Clone before inserting
-        let avoid_2 = avoid.clone();
-
-        if node != "start" && node.to_uppercase() != *node && avoid.insert(node.clone()) && !skipped
-        {
-            // node was avoided for the first time
-            // split (duplicate) paths when small cave is avoided
-            // to path where it is avoided and a path where it is not
-            alt_paths = initial_neighbours
-                .clone()
-                .filter(|n| *n != "start" && !avoid_2.contains(*n))
-                .map(|n| path_count(n, graph, avoid_2.clone(), true, path.clone(), paths))
-                .fold(0, |acc, c| acc + c);
+            .filter(|n| **n != self.start)
+            .map(|&n| {
+                let seen = visited & (1 << n) != 0;
+                match (self.small[n], seen) {
+                    (false, _) => self.count_from(n, visited, revisits, memo),
+                    (true, false) => self.count_from(n, visited | 1 << n, revisits, memo),
+                    (true, true) if revisits > 0 => self.count_from(n, visited, revisits - 1, memo),
+                    _ => 0,
+                }
+            })
+            .sum();
+
+        memo.insert((node, visited, revisits), count);
+        count
+    }
+
+    pub fn paths(&self, revisits: usize) -> Paths<'_> {
+        let mut visits = vec![0; self.names.len()];
+        visits[self.start] = 1;
+        Paths {
+            graph: self,
+            stack: vec![(self.start, 0, false)],
+            visits,
+            revisits,
+        }
+    }
+}
+
+// Depth-first enumeration of every path from start to end, with the same
+// revisit budget as count_paths
+pub struct Paths<'a> {
+    graph: &'a CaveGraph,
+    stack: Vec<(usize, usize, bool)>, // cave, next neighbour index, entered as a revisit
+    visits: Vec<usize>,
+    revisits: usize,
+}
+
+impl Paths<'_> {
+    fn backtrack(&mut self) {
+        if let Some((node, _, revisit)) = self.stack.pop() {
+            self.visits[node] -= 1;
+            self.revisits += revisit as usize;
         }
+    }
+}
 
-        initial_neighbours
-            .filter(|n| *n != "start" && !avoid.contains(*n))
-            .map(|n| path_count(n, graph, avoid.clone(), skipped, path.clone(), paths))
-            .fold(0, |acc, c| acc + c)
-            + alt_paths
+impl Iterator for Paths<'_> {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let g = self.graph;
+        loop {
+            let (node, i, _) = self.stack.last_mut()?;
+            let node = *node;
+
+            if node == g.end {
+                let path = self.stack.iter().map(|f| g.names[f.0].clone()).collect();
+                self.backtrack();
+                return Some(path);
+            }
+
+            if *i == g.adj[node].len() {
+                self.backtrack();
+                continue;
+            }
+
+            let n = g.adj[node][*i];
+            *i += 1;
+
+            let revisit = g.small[n] && self.visits[n] > 0;
+            if n == g.start || (revisit && (self.revisits == 0 || n == g.end)) {
+                continue;
+            }
+            self.revisits -= revisit as usize;
+            self.visits[n] += 1;
+            self.stack.push((n, 0, revisit));
+        }
     }
 }
 
 pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    let edges: Vec<Edge> = input
-        .iter()
-        .map(|s| s.split("-").map(|s| String::from(s)).collect())
-        .map(|v: Vec<String>| (v[0].clone(), v[1].clone()))
-        .collect();
-
-    let mut paths: HashSet<String> = HashSet::new();
-    let start = String::from("start");
-    path_count(&start, &edges, HashSet::new(), true, vec![], &mut paths)
+    CaveGraph::parse(&input).unwrap().count_paths(0)
 }
 
 pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    let edges: Vec<Edge> = input
-        .iter()
-        .map(|s| s.split("-").map(|s| String::from(s)).collect())
-        .map(|v: Vec<String>| (v[0].clone(), v[1].clone()))
-        .collect();
-
-    let mut paths: HashSet<String> = HashSet::new();
-    let start = String::from("start");
-
-    path_count(&start, &edges, HashSet::new(), false, vec![], &mut paths)
+    CaveGraph::parse(&input).unwrap().count_paths(1)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day12;
+    use std::collections::HashSet;
 
     #[test]
     fn test1() {
@@ -95,6 +191,35 @@ mod tests {
         assert_eq!(day12::puzzle2("src/y2021/day12/test.txt"), 36);
         assert_eq!(day12::puzzle2("src/y2021/day12/test2.txt"), 103);
         assert_eq!(day12::puzzle2("src/y2021/day12/test3.txt"), 3509);
-        // assert_eq!(day12::puzzle2("src/y2021/day12/input.txt"), 93686);
+        assert_eq!(day12::puzzle2("src/y2021/day12/input.txt"), 93686);
+    }
+
+    #[test]
+    fn test_paths() {
+        let input = lib::read_lines("src/y2021/day12/test.txt");
+        let graph = day12::CaveGraph::parse(&input).unwrap();
+
+        let paths: Vec<String> = graph.paths(0).map(|p| p.join(",")).collect();
+        assert_eq!(paths.len(), 10);
+        assert!(paths.contains(&"start,A,b,A,c,A,end".to_string()));
+        assert_eq!(paths.iter().collect::<HashSet<_>>().len(), 10);
+
+        for revisits in 0..3 {
+            assert_eq!(graph.paths(revisits).count(), graph.count_paths(revisits));
+        }
+
+        // A budget of two can be spent on one cave or on two different ones
+        assert_eq!(graph.count_paths(2), 101);
+        assert_eq!(graph.paths(2).count(), 101);
+        let paths: Vec<String> = graph.paths(2).map(|p| p.join(",")).collect();
+        assert!(paths.contains(&"start,A,c,A,c,A,c,A,end".to_string()));
+        assert!(paths.contains(&"start,A,b,A,b,A,c,A,c,A,end".to_string()));
+        assert!(!paths.contains(&"start,A,c,A,c,A,c,A,c,A,end".to_string()));
+
+        let loops: Vec<String> = ["start-A", "A-B", "B-end"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(day12::CaveGraph::parse(&loops).is_err());
     }
 }
@@ -1,178 +1,147 @@
 use std::collections::HashSet;
+use std::fmt;
 
 use crate::lib;
+use crate::lib::grid::{self, Axis};
 
-pub fn puzzle1(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let mut dots: Vec<lib::Point> = input
-        .iter()
-        .map(|s| {
-            s.split(",")
-                .filter_map(|s| s.parse::<usize>().ok())
-                .collect()
-        })
-        .filter_map(|v: Vec<usize>| {
-            if v.len() == 2 {
-                Some(lib::Point::new(v[0], v[1]))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let folds: Vec<lib::Point> = input
-        .iter()
-        .filter(|s| s.starts_with("fold along"))
-        .map(|s| s.replace("fold along ", ""))
-        .map(|s| {
-            let ss: Vec<&str> = s.split("=").collect();
-            let v = ss[1].parse::<usize>().unwrap();
-            if ss[0] == "x" {
-                vec![v, 0]
-            } else {
-                vec![0, v]
-            }
-        })
-        .filter_map(|v: Vec<usize>| {
-            if v.len() == 2 {
-                Some(lib::Point::new(v[0], v[1]))
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fold {
+    X(usize), // fold the right half over to the left
+    Y(usize), // fold the bottom half up
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paper {
+    dots: HashSet<lib::Point>,
+}
+
+impl Fold {
+    pub fn parse(s: &str) -> Result<Fold, String> {
+        let (axis, v) = s
+            .strip_prefix("fold along ")
+            .and_then(|s| s.split_once('='))
+            .ok_or(format!("invalid fold \"{}\"", s))?;
+        let v = v
+            .parse()
+            .map_err(|_| format!("invalid fold line \"{}\"", s))?;
+        match axis {
+            "x" => Ok(Fold::X(v)),
+            "y" => Ok(Fold::Y(v)),
+            _ => Err(format!("invalid fold axis \"{}\"", axis)),
+        }
+    }
+}
+
+impl Paper {
+    pub fn parse(input: &[String]) -> Result<(Paper, Vec<Fold>), String> {
+        let mut dots = HashSet::new();
+        let mut folds = vec![];
+        for line in input.iter().filter(|l| !l.is_empty()) {
+            if line.starts_with("fold") {
+                folds.push(Fold::parse(line)?);
             } else {
-                None
+                let (x, y) = line
+                    .split_once(',')
+                    .ok_or(format!("invalid dot \"{}\"", line))?;
+                let parse = |v: &str| v.parse().map_err(|_| format!("invalid dot \"{}\"", line));
+                dots.insert(lib::Point::new(parse(x)?, parse(y)?));
             }
-        })
-        .collect();
-
-    for fold in folds.iter().take(1) {
-        if fold.x > 0 {
-            dots = dots
-                .into_iter()
-                .map(|d| {
-                    if d.x > fold.x {
-                        // fold along y (change x)
-                        let new_x = fold.x - (d.x - fold.x);
-                        lib::Point::new(new_x, d.y)
-                    } else {
-                        d
-                    }
-                })
-                .collect()
-        } else if fold.y > 0 {
-            dots = dots
-                .into_iter()
-                .map(|d| {
-                    if d.y > fold.y {
-                        // fold along x (change y)
-                        let new_y = fold.y - (d.y - fold.y);
-                        lib::Point::new(d.x, new_y)
-                    } else {
-                        d
-                    }
-                })
-                .collect()
         }
+        Ok((Paper { dots }, folds))
     }
 
-    let mut r: HashSet<lib::Point> = HashSet::new();
-    for d in dots.iter() {
-        r.insert(*d);
+    pub fn len(&self) -> usize {
+        self.dots.len()
     }
 
-    r.len()
-}
+    pub fn dots(&self) -> Vec<lib::Point> {
+        self.dots.iter().copied().collect()
+    }
 
-pub fn puzzle2(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let mut dots: Vec<lib::Point> = input
-        .iter()
-        .map(|s| {
-            s.split(",")
-                .filter_map(|s| s.parse::<usize>().ok())
-                .collect()
-        })
-        .filter_map(|v: Vec<usize>| {
-            if v.len() == 2 {
-                Some(lib::Point::new(v[0], v[1]))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let folds: Vec<lib::Point> = input
-        .iter()
-        .filter(|s| s.starts_with("fold along"))
-        .map(|s| s.replace("fold along ", ""))
-        .map(|s| {
-            let ss: Vec<&str> = s.split("=").collect();
-            let v = ss[1].parse::<usize>().unwrap();
-            if ss[0] == "x" {
-                vec![v, 0]
-            } else {
-                vec![0, v]
-            }
-        })
-        .filter_map(|v: Vec<usize>| {
-            if v.len() == 2 {
-                Some(lib::Point::new(v[0], v[1]))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    for fold in folds.iter() {
-        if fold.x > 0 {
-            dots = dots
-                .into_iter()
-                .map(|d| {
-                    if d.x > fold.x {
-                        // fold along y (change x)
-                        lib::Point::new(fold.x - (d.x - fold.x), d.y)
-                    } else {
-                        d
-                    }
-                })
-                .collect()
-        } else if fold.y > 0 {
-            dots = dots
-                .into_iter()
-                .map(|d| {
-                    if d.y > fold.y {
-                        // fold along x (change y)
-                        lib::Point::new(d.x, fold.y - (d.y - fold.y))
-                    } else {
-                        d
-                    }
-                })
-                .collect()
+    // Folds the sheet as a grid, dots landing on each other merge. grid::fold
+    // drops the crease, so dots on it are put back where it ends up, just past
+    // the folded cells. When the folded part is larger than the rest (e.g. a fold
+    // at x=0) the sheet flips over and the result still starts at the origin.
+    pub fn fold(&self, fold: &Fold) -> Paper {
+        let (axis, line) = match *fold {
+            Fold::X(line) => (Axis::X, line),
+            Fold::Y(line) => (Axis::Y, line),
+        };
+        let mut s = lib::Shape::new_from_points(&self.dots());
+        match axis {
+            Axis::X => s.w = s.w.max(line + 1),
+            Axis::Y => s.h = s.h.max(line + 1),
+        }
+        let mut sheet = vec![false; s.len()];
+        for d in &self.dots {
+            sheet[lib::point_to_i(&s, d)] = true;
         }
+
+        let (to, sheet) = grid::fold(&s, &sheet, axis, line, |a, b| *a || *b).unwrap();
+        let mut dots: HashSet<lib::Point> = (0..to.len())
+            .filter(|i| sheet[*i])
+            .map(|i| lib::i_to_point(&to, i))
+            .collect();
+        dots.extend(self.dots.iter().filter_map(|d| match axis {
+            Axis::X if d.x == line => Some(lib::Point::new(to.w, d.y)),
+            Axis::Y if d.y == line => Some(lib::Point::new(d.x, to.h)),
+            _ => None,
+        }));
+        Paper { dots }
     }
 
-    let mut r: HashSet<lib::Point> = HashSet::new();
-    for d in dots.iter() {
-        r.insert(*d);
+    // Paper after each fold in turn
+    pub fn folds<'a>(&self, folds: &'a [Fold]) -> impl Iterator<Item = Paper> + 'a {
+        folds.iter().scan(self.clone(), |paper, f| {
+            *paper = paper.fold(f);
+            Some(paper.clone())
+        })
     }
 
-    let s = lib::Shape::new_from_points(&dots);
+    pub fn after(&self, folds: &[Fold], n: usize) -> Paper {
+        folds
+            .iter()
+            .take(n)
+            .fold(self.clone(), |paper, f| paper.fold(f))
+    }
+}
 
-    for y in 0..s.h {
-        for x in 0..s.w {
-            if dots.iter().find(|d| d.x == x && d.y == y).is_some() {
-                print!("#");
-            } else {
-                print!(".");
+impl fmt::Display for Paper {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = lib::Shape::new_from_points(&self.dots());
+        for y in 0..s.h {
+            for x in 0..s.w {
+                match self.dots.contains(&lib::Point::new(x, y)) {
+                    true => write!(f, "#")?,
+                    false => write!(f, ".")?,
+                }
             }
+            writeln!(f)?;
         }
-        println!();
+        Ok(())
     }
-    println!();
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
+    let input = lib::read_lines(input_filename);
+    let (paper, folds) = Paper::parse(&input).unwrap();
+
+    paper.after(&folds, 1).len()
+}
+
+pub fn puzzle2(input_filename: &str) -> String {
+    let input = lib::read_lines(input_filename);
+    let (paper, folds) = Paper::parse(&input).unwrap();
 
-    r.len()
+    let folded = paper.after(&folds, folds.len());
+    lib::ocr::recognize_points(&folded.dots()).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day13;
+    use crate::y2021::day13::{Fold, Paper};
 
     #[test]
     fn test1() {
@@ -182,7 +151,22 @@ mod tests {
 
     #[test]
     fn test2() {
-        assert_eq!(day13::puzzle2("src/y2021/day13/test.txt"), 16);
-        // assert_eq!(day13::puzzle2("src/y2021/day13/input.txt"), 1);
+        assert_eq!(day13::puzzle2("src/y2021/day13/input.txt"), "HECRZKPR");
+    }
+
+    #[test]
+    fn test_paper() {
+        let input = lib::read_lines("src/y2021/day13/test.txt");
+        let (paper, folds) = Paper::parse(&input).unwrap();
+        assert_eq!(folds, vec![Fold::Y(7), Fold::X(5)]);
+
+        let states: Vec<Paper> = paper.folds(&folds).collect();
+        assert_eq!(states.iter().map(|p| p.len()).collect::<Vec<_>>(), [17, 16]);
+        assert_eq!(states[1], paper.after(&folds, 2));
+        assert_eq!(states[1].to_string(), "#####\n#...#\n#...#\n#...#\n#####\n");
+
+        let flipped = paper.fold(&Fold::X(0));
+        assert_eq!(flipped.len(), paper.len());
+        assert_eq!(flipped.fold(&Fold::X(0)), paper);
     }
 }
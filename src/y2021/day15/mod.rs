@@ -1,21 +1,14 @@
 use crate::lib;
+use crate::lib::grid;
 
-fn mapped_value(values: &Vec<u8>, orig_shape: &lib::Shape, shape: &lib::Shape, i: usize) -> u8 {
-    let scaled_p = lib::i_to_point(shape, i);
-    let orig_p = lib::Point::new(scaled_p.x % orig_shape.w, scaled_p.y % orig_shape.h);
-
-    let v = values[lib::point_to_i(orig_shape, &orig_p)];
-
-    if orig_p == scaled_p {
-        return v;
-    }
-
-    let c = lib::Point::new(scaled_p.x / orig_shape.w, scaled_p.y / orig_shape.h);
-
-    (v - 1 + (c.x + c.y) as u8) % 9 + 1
+// Each tile to the right or down adds one to the risk, wrapping from 9 to 1
+fn tiled_risk(shape: &lib::Shape, values: &[u8], tiles: usize) -> (lib::Shape, Vec<u8>) {
+    grid::tile(shape, values, lib::Shape::new(tiles, tiles), |v, t| {
+        (v - 1 + ((t.x + t.y) % 9) as u8) % 9 + 1
+    })
 }
 
-pub fn puzzle1(input_filename: &str) -> usize {
+fn parse_risk(input_filename: &str) -> (lib::Shape, Vec<u8>) {
     let input = lib::read_lines(input_filename);
 
     let shape = lib::Shape::new(input[0].len(), input.len());
@@ -26,34 +19,59 @@ pub fn puzzle1(input_filename: &str) -> usize {
         .map(|c: char| c.to_digit(10).unwrap() as u8)
         .collect();
 
-    *lib::dijkstra(&shape, 0, shape.len() - 1, |v| values[v] as usize)
+    (shape, values)
+}
+
+// Risks are 1 to 9, so Dial's buckets beat a binary heap
+fn lowest_total_risk<F>(shape: &lib::Shape, risk: F) -> usize
+where
+    F: Fn(usize) -> usize,
+{
+    let neighbours = |u| lib::neighbours(shape, u, false);
+    let frontier = lib::Frontier::Buckets { max_cost: 9 };
+    *lib::dijkstra_by(shape, 0, shape.len() - 1, neighbours, risk, frontier)
         .last() // target is the last in the array
         .unwrap()
 }
 
-pub fn puzzle2(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
+pub fn puzzle1(input_filename: &str) -> usize {
+    let (shape, values) = parse_risk(input_filename);
 
-    let shape = lib::Shape::new(input[0].len(), input.len());
+    lowest_total_risk(&shape, |v| values[v] as usize)
+}
 
-    let values: Vec<u8> = input
-        .iter()
-        .flat_map(|s| s.chars())
-        .map(|c: char| c.to_digit(10).unwrap() as u8)
-        .collect();
+pub fn puzzle2(input_filename: &str) -> usize {
+    let (shape, values) = parse_risk(input_filename);
 
-    let scaled_shape = shape * 5;
+    let (scaled_shape, scaled) = tiled_risk(&shape, &values, 5);
 
-    *lib::dijkstra(&scaled_shape, 0, scaled_shape.len() - 1, |v| {
-        mapped_value(&values, &shape, &scaled_shape, v) as usize
-    })
-    .last() // target is the last in the array
-    .unwrap()
+    lowest_total_risk(&scaled_shape, |v| scaled[v] as usize)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
+    use crate::lib::Frontier::{self, Buckets, Heap, ZeroOne};
     use crate::y2021::day15;
+    use std::time::{Duration, Instant};
+
+    fn part2_risk() -> (lib::Shape, Vec<usize>) {
+        let (shape, values) = day15::parse_risk("src/y2021/day15/input.txt");
+        let (scaled, risk) = day15::tiled_risk(&shape, &values, 5);
+        (scaled, risk.iter().map(|v| *v as usize).collect())
+    }
+
+    // Best of a few runs
+    fn best_of(run: &dyn Fn() -> usize) -> (Duration, usize) {
+        (0..5)
+            .map(|_| {
+                let t = Instant::now();
+                let res = run();
+                (t.elapsed(), res)
+            })
+            .min()
+            .unwrap()
+    }
 
     #[test]
     fn test1() {
@@ -66,4 +84,89 @@ mod tests {
         assert_eq!(day15::puzzle2("src/y2021/day15/test.txt"), 315);
         assert_eq!(day15::puzzle2("src/y2021/day15/input.txt"), 2817);
     }
+
+    #[test]
+    fn test_frontiers() {
+        let (shape, values) = day15::parse_risk("src/y2021/day15/test.txt");
+        let risk: Vec<usize> = values.iter().map(|v| *v as usize).collect();
+        let target = shape.len() - 1;
+        let search = |cost: &dyn Fn(usize) -> usize, frontier: Frontier| {
+            let neighbours = |u| lib::neighbours(&shape, u, false);
+            lib::dijkstra_by(&shape, 0, target, neighbours, cost, frontier)[target]
+        };
+
+        let risk_of = |v: usize| risk[v];
+        assert_eq!(search(&risk_of, Heap), 40);
+        assert_eq!(search(&risk_of, Buckets { max_cost: 9 }), 40);
+
+        // Only the riskiest cells cost anything
+        let risky = |v: usize| (risk[v] > 6) as usize;
+        assert_eq!(search(&risky, ZeroOne), search(&risky, Heap));
+        assert_eq!(
+            search(&risky, Buckets { max_cost: 1 }),
+            search(&risky, Heap)
+        );
+    }
+
+    // Part 2 with the heap against Dial's buckets, run with
+    // cargo test --release -- --ignored bench_frontiers --nocapture
+    #[test]
+    #[ignore]
+    fn bench_frontiers() {
+        let (scaled, risk) = part2_risk();
+        let target = scaled.len() - 1;
+
+        for frontier in [Heap, Buckets { max_cost: 9 }] {
+            let (elapsed, cost) = best_of(&|| {
+                let neighbours = |u| lib::neighbours(&scaled, u, false);
+                lib::dijkstra_by(&scaled, 0, target, neighbours, |v| risk[v], frontier)[target]
+            });
+            println!("{:?} {:?}", frontier, elapsed);
+            assert_eq!(cost, 2817);
+        }
+    }
+
+    // Neighbour lookups compared on part 2, run with
+    // cargo test --release -- --ignored bench_neighbours --nocapture
+    #[test]
+    #[ignore]
+    fn bench_neighbours() {
+        let (scaled, risk) = part2_risk();
+        let target = scaled.len() - 1;
+
+        // What lib::neighbours did before it became an iterator
+        let allocating = |u: usize| {
+            let p = lib::i_to_point(&scaled, u);
+            let dirs: Vec<fn(&lib::Shape, &lib::Point) -> Option<lib::Point>> =
+                vec![lib::top, lib::bottom, lib::left, lib::right];
+            dirs.into_iter()
+                .filter_map(|f| f(&scaled, &p))
+                .map(|p| lib::point_to_i(&scaled, &p))
+                .collect::<Vec<usize>>()
+        };
+
+        let (t_allocating, a) = best_of(&|| {
+            lib::dijkstra_by(&scaled, 0, target, allocating, |v| risk[v], Heap)[target]
+        });
+        let (t_iterator, b) = best_of(&|| {
+            let neighbours = |u| lib::neighbours(&scaled, u, false);
+            lib::dijkstra_by(&scaled, 0, target, neighbours, |v| risk[v], Heap)[target]
+        });
+
+        let t = Instant::now();
+        let adjacency = scaled.adjacency(false);
+        let t_table = t.elapsed();
+        let (t_adjacency, c) = best_of(&|| {
+            let neighbours = |u| adjacency.get(u).iter().copied();
+            lib::dijkstra_by(&scaled, 0, target, neighbours, |v| risk[v], Heap)[target]
+        });
+
+        println!("allocating {:?}", t_allocating);
+        println!("iterator   {:?}", t_iterator);
+        println!(
+            "adjacency  {:?} (+ {:?} to build the table)",
+            t_adjacency, t_table
+        );
+        assert_eq!((a, b, c), (2817, 2817, 2817));
+    }
 }
@@ -1,4 +1,6 @@
 use crate::lib;
+use crate::lib::bigint::BigUint;
+use std::ops::{Add, Mul};
 
 const HEADER_LEN: usize = 6;
 const LITERAL_VALUE_LEN: usize = 5;
@@ -34,17 +36,21 @@ impl PacketType {
         }
     }
 
-    pub fn eval(&self, p: &Packet) -> usize {
-        let iter = p.sub_packets.iter();
+    // Generic over the value type so large expressions can be evaluated with a BigUint
+    pub fn eval<T>(&self, p: &Packet) -> T
+    where
+        T: Clone + Ord + From<usize> + Add<Output = T> + Mul<Output = T>,
+    {
+        let mut iter = p.sub_packets.iter().map(|c| c.value_as::<T>());
         match self {
-            PacketType::LIT => p.value,
-            PacketType::SUM => iter.fold(0, |acc, c| acc + c.value()),
-            PacketType::PROD => iter.fold(1, |acc, c| acc * c.value()),
-            PacketType::MIN => iter.map(|p| p.value()).min().unwrap_or_default(),
-            PacketType::MAX => iter.map(|p| p.value()).max().unwrap_or_default(),
-            PacketType::GT => (p.sub_packets[0].value() > p.sub_packets[1].value()) as usize,
-            PacketType::LT => (p.sub_packets[0].value() < p.sub_packets[1].value()) as usize,
-            PacketType::EQ => (p.sub_packets[0].value() == p.sub_packets[1].value()) as usize,
+            PacketType::LIT => T::from(p.value),
+            PacketType::SUM => iter.fold(T::from(0), |acc, v| acc + v),
+            PacketType::PROD => iter.fold(T::from(1), |acc, v| acc * v),
+            PacketType::MIN => iter.min().unwrap_or_else(|| T::from(0)),
+            PacketType::MAX => iter.max().unwrap_or_else(|| T::from(0)),
+            PacketType::GT => T::from((iter.next() > iter.next()) as usize),
+            PacketType::LT => T::from((iter.next() < iter.next()) as usize),
+            PacketType::EQ => T::from((iter.next() == iter.next()) as usize),
         }
     }
 }
@@ -152,6 +158,13 @@ impl Packet {
     }
 
     pub fn value(&self) -> usize {
+        self.value_as()
+    }
+
+    pub fn value_as<T>(&self) -> T
+    where
+        T: Clone + Ord + From<usize> + Add<Output = T> + Mul<Output = T>,
+    {
         self.header.r#type.eval(self)
     }
 }
@@ -197,6 +210,14 @@ pub fn puzzle2(input_filename: &str) -> usize {
     p.value()
 }
 
+pub fn puzzle2_big(input_filename: &str) -> BigUint {
+    let input = lib::read_lines(input_filename);
+
+    let p = Packet::from_hex(&input[0]).unwrap();
+
+    p.value_as()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::y2021::day16;
@@ -218,5 +239,9 @@ mod tests {
         assert_eq!(day16::puzzle2("src/y2021/day16/test8.txt"), 0);
         assert_eq!(day16::puzzle2("src/y2021/day16/test9.txt"), 1);
         assert_eq!(day16::puzzle2("src/y2021/day16/input.txt"), 470949537659);
+        assert_eq!(
+            day16::puzzle2_big("src/y2021/day16/input.txt").to_string(),
+            "470949537659"
+        );
     }
 }
@@ -1,8 +1,9 @@
 use crate::lib;
-use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
+// Target bounds, x1..=x2 from left to right and y1..=y2 from top to bottom
 #[derive(Debug, Clone, Copy)]
-struct Area {
+pub struct Area {
     x1: isize,
     x2: isize,
     y1: isize,
@@ -24,16 +25,48 @@ impl Area {
         px >= &self.x1 && px <= &self.x2 && py <= &self.y1 && py >= &self.y2
     }
 
-    pub fn point_has_passed(&self, p: &Point) -> bool {
-        let Point { x: px, y: py } = p;
-        px > &self.x2 || py < &self.y2
+    // Drag never reverses x and gravity eventually pulls y down for good, so once
+    // the probe is past the area in its direction of travel it can never come back
+    pub fn point_has_passed(&self, p: &Point, velocity: &Point) -> bool {
+        let past_x = match velocity.x.signum() {
+            1 => p.x > self.x2,
+            -1 => p.x < self.x1,
+            _ => p.x < self.x1 || p.x > self.x2,
+        };
+        past_x || (p.y < self.y2 && velocity.y < 0)
+    }
+
+    fn x_stops_inside(&self, vx: isize) -> bool {
+        let stop = vx.signum() * vx.abs() * (vx.abs() + 1) / 2;
+        stop >= self.x1 && stop <= self.x2
+    }
+
+    // Any faster initial x velocity overshoots the area on the first step
+    fn x_velocities(&self) -> RangeInclusive<isize> {
+        self.x1.min(0)..=self.x2.max(0)
+    }
+
+    // Moving up, the probe later passes the same heights on its way down and then
+    // falls with a speed of at least the initial one, so it can only land in the
+    // area if that speed does not exceed the distance to the far edge. When the
+    // area straddles y = 0 the probe may also hit it early while still climbing,
+    // which it has at most max(|x|) steps to do before x leaves the area.
+    fn y_velocities(&self) -> Result<RangeInclusive<isize>, String> {
+        let mut hi = self.y1.abs().max(self.y2.abs());
+        if self.y2 <= 0 && self.y1 >= 0 {
+            if self.x_velocities().any(|vx| self.x_stops_inside(vx)) {
+                return Err("probe can rest above the area, velocities are unbounded".to_string());
+            }
+            hi += self.x1.abs().max(self.x2.abs());
+        }
+        Ok(self.y2.min(0)..=hi)
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    x: isize,
-    y: isize,
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -43,17 +76,16 @@ struct State {
     velocity: Point,
 }
 
+#[derive(Debug, Clone)]
+pub struct Shot {
+    pub velocity: Point,
+    pub trajectory: Vec<Point>, // positions after each step, ending inside the area
+    pub apex: isize,
+}
+
 impl Iterator for State {
     type Item = State;
     fn next(&mut self) -> Option<Self::Item> {
-        let x_vel = if self.velocity.x > 0 {
-            self.velocity.x - 1
-        } else if self.velocity.x < 0 {
-            self.velocity.x + 1
-        } else {
-            self.velocity.x
-        };
-
         let new_state = State {
             area: self.area,
             point: Point {
@@ -61,95 +93,89 @@ impl Iterator for State {
                 y: self.point.y + self.velocity.y,
             },
             velocity: Point {
-                x: x_vel,
+                x: self.velocity.x - self.velocity.x.signum(),
                 y: self.velocity.y - 1,
             },
         };
 
-        if new_state.area.point_has_passed(&new_state.point) {
+        if new_state
+            .area
+            .point_has_passed(&new_state.point, &new_state.velocity)
+        {
             None
         } else {
+            *self = new_state;
             Some(new_state)
         }
     }
 }
 
-fn parse_area(s: &str) -> Area {
-    s.replace("target area: ", "")
+pub fn parse_area(s: &str) -> Area {
+    let c: Vec<isize> = s
+        .replace("target area: ", "")
         .replace("x=", "")
         .replace("y=", "")
         .split(",")
         .flat_map(|s| s.split(".."))
         .filter_map(|s| s.trim().to_string().parse().ok())
-        .enumerate()
-        .fold(Area::new(), |mut area, (i, c)| {
-            match i {
-                0 => area.x1 = c,
-                1 => area.x2 = c,
-                2 => area.y2 = c,
-                3 => area.y1 = c,
-                _ => {}
-            }
-            area
-        })
+        .collect();
+
+    let mut area = Area::new();
+    if let [x1, x2, y1, y2] = c[..] {
+        area.x1 = x1.min(x2);
+        area.x2 = x1.max(x2);
+        area.y1 = y1.max(y2);
+        area.y2 = y1.min(y2);
+    }
+    area
+}
+
+fn shoot(area: Area, velocity: Point) -> Option<Shot> {
+    let start = State {
+        area,
+        point: Point { x: 0, y: 0 },
+        velocity,
+    };
+    let mut trajectory = vec![];
+    for s in start {
+        trajectory.push(s.point);
+        if area.contains_point(&s.point) {
+            return Some(Shot {
+                velocity,
+                trajectory,
+                apex: velocity.y.max(0) * (velocity.y.max(0) + 1) / 2,
+            });
+        }
+    }
+    None
+}
+
+pub fn valid_shots(area: Area) -> Result<Vec<Shot>, String> {
+    let ys = area.y_velocities()?;
+    Ok(area
+        .x_velocities()
+        .flat_map(|x| ys.clone().map(move |y| Point { x, y }))
+        .filter_map(|v| shoot(area, v))
+        .collect())
 }
 
 pub fn puzzle1(input_filename: &str) -> isize {
     let input = lib::read_lines(input_filename);
     let area = parse_area(&input[0]);
 
-    let mut max_y_pos = 0;
-
-    for x_vel in 0..100 {
-        for y_vel in -500..500 {
-            let mut state = State {
-                area,
-                point: Point { x: 0, y: 0 },
-                velocity: Point { x: x_vel, y: y_vel },
-            };
-            let mut hist: Vec<isize> = vec![];
-            while let Some(s) = state.next() {
-                state = s;
-                hist.push(s.point.y);
-                if s.area.contains_point(&s.point) {
-                    let max_y = hist.into_iter().max().unwrap_or_default();
-                    if max_y > max_y_pos {
-                        max_y_pos = max_y;
-                    }
-                    break;
-                }
-            }
-        }
-    }
-
-    max_y_pos
+    valid_shots(area)
+        .unwrap()
+        .iter()
+        .map(|s| s.apex)
+        .max()
+        .unwrap_or_default()
 }
 
 pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
     let area = parse_area(&input[0]);
-    let start = Point { x: 0, y: 0 };
-
-    let mut valids = 0;
-
-    for x_vel in 0..1000 {
-        for y_vel in -500..500 {
-            let mut state = State {
-                area,
-                point: start,
-                velocity: Point { x: x_vel, y: y_vel },
-            };
-            while let Some(s) = state.next() {
-                state = s;
-                if s.area.contains_point(&s.point) {
-                    valids += 1;
-                    break;
-                }
-            }
-        }
-    }
 
-    valids
+    valid_shots(area).unwrap().len()
 }
 
 #[cfg(test)]
@@ -167,4 +193,27 @@ mod tests {
         assert_eq!(day17::puzzle2("src/y2021/day17/test.txt"), 112);
         assert_eq!(day17::puzzle2("src/y2021/day17/input.txt"), 1919);
     }
+
+    #[test]
+    fn test_quadrants() {
+        let shots = |s| day17::valid_shots(day17::parse_area(s)).unwrap();
+
+        let left = shots("target area: x=-30..-20, y=-10..-5");
+        assert_eq!(left.len(), 112);
+        assert_eq!(left.iter().map(|s| s.apex).max(), Some(45));
+
+        let above = shots("target area: x=20..30, y=5..10");
+        assert_eq!(above.len(), 103);
+        assert_eq!(above.iter().map(|s| s.apex).max(), Some(55));
+
+        let below = shots("target area: x=-3..4, y=-10..-5");
+        assert_eq!(below.len(), 118);
+
+        let shot = &left[0];
+        assert!(day17::parse_area("target area: x=-30..-20, y=-10..-5")
+            .contains_point(shot.trajectory.last().unwrap()));
+
+        let origin = day17::parse_area("target area: x=-3..4, y=-2..5");
+        assert!(day17::valid_shots(origin).is_err());
+    }
 }
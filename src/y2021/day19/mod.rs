@@ -1,13 +1,90 @@
+use std::collections::HashSet;
+
 use crate::lib;
+use crate::lib::space::{align, Point3, Transform};
 
-pub fn puzzle1(input_filename: &str) -> isize {
-    let input = lib::read_lines(input_filename);
-    0
+const MIN_OVERLAP: usize = 12;
+
+#[derive(Debug)]
+struct Scanner {
+    beacons: Vec<Point3>,
+}
+
+#[derive(Debug)]
+struct BeaconMap {
+    beacons: HashSet<Point3>,
+    scanners: Vec<Point3>,
+}
+
+fn parse_scanners(input: &[String]) -> Result<Vec<Scanner>, String> {
+    let mut scanners: Vec<Scanner> = vec![];
+    for line in input.iter().filter(|l| !l.is_empty()) {
+        if line.starts_with("---") {
+            scanners.push(Scanner { beacons: vec![] });
+        } else {
+            scanners
+                .last_mut()
+                .ok_or("beacon listed before any scanner header")?
+                .beacons
+                .push(Point3::parse(line)?);
+        }
+    }
+    Ok(scanners)
+}
+
+// Aligns every scanner into the frame of scanner 0, always matching against
+// scanners that have already been placed
+fn assemble(scanners: &[Scanner]) -> Result<BeaconMap, String> {
+    let mut transforms: Vec<Option<Transform>> = vec![None; scanners.len()];
+    let mut frontier = vec![0];
+    transforms[0] = Some(Transform::identity());
+
+    while let Some(i) = frontier.pop() {
+        let ti = transforms[i].unwrap();
+        for j in 0..scanners.len() {
+            if transforms[j].is_some() {
+                continue;
+            }
+            if let Some(t) = align(&scanners[i].beacons, &scanners[j].beacons, MIN_OVERLAP) {
+                transforms[j] = Some(ti.compose(&t));
+                frontier.push(j);
+            }
+        }
+    }
+
+    let transforms: Vec<Transform> = transforms
+        .into_iter()
+        .enumerate()
+        .map(|(i, t)| t.ok_or(format!("scanner {} does not overlap any other", i)))
+        .collect::<Result<_, _>>()?;
+
+    Ok(BeaconMap {
+        beacons: scanners
+            .iter()
+            .zip(transforms.iter())
+            .flat_map(|(s, t)| s.beacons.iter().map(|b| t.apply(b)))
+            .collect(),
+        scanners: transforms.iter().map(|t| t.translation).collect(),
+    })
 }
 
-pub fn puzzle2(input_filename: &str) -> isize {
+fn beacon_map(input_filename: &str) -> BeaconMap {
     let input = lib::read_lines(input_filename);
-    0
+    let scanners = parse_scanners(&input).unwrap();
+    assemble(&scanners).unwrap()
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
+    beacon_map(input_filename).beacons.len()
+}
+
+pub fn puzzle2(input_filename: &str) -> usize {
+    let map = beacon_map(input_filename);
+    map.scanners
+        .iter()
+        .flat_map(|a| map.scanners.iter().map(|b| a.manhattan(b)))
+        .max()
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -16,13 +93,13 @@ mod tests {
 
     #[test]
     fn test1() {
-        assert_eq!(day19::puzzle1("src/y2021/day19/test.txt"), 1);
-        assert_eq!(day19::puzzle1("src/y2021/day19/input.txt"), 1);
+        assert_eq!(day19::puzzle1("src/y2021/day19/test.txt"), 79);
+        // assert_eq!(day19::puzzle1("src/y2021/day19/input.txt"), 1);
     }
 
     #[test]
     fn test2() {
-        assert_eq!(day19::puzzle2("src/y2021/day19/test.txt"), 1);
-        assert_eq!(day19::puzzle2("src/y2021/day19/input.txt"), 1);
+        assert_eq!(day19::puzzle2("src/y2021/day19/test.txt"), 3621);
+        // assert_eq!(day19::puzzle2("src/y2021/day19/input.txt"), 1);
     }
 }
@@ -32,12 +32,11 @@ fn enhance(iea: &Vec<char>, padder: &mut char, img: &mut Vec<char>, shape: &mut
 
     for i in 0..p_img.len() {
         // Get the neighbours of the current pixel and convert them to bools.
-        // 'neighbours_2' returns a vector of Option<usize>, of indexes of the neighbours.
+        // 'neighbours_2' yields Option<usize>, indexes of the neighbours.
         // The Option is None when we are looking at a pixel outside the shape.
         let mut bits = lib::neighbours_2(&p_shape, i, true)
-            .iter()
             .map(|j| match j {
-                Some(j) => p_img[*j] == '#',
+                Some(j) => p_img[j] == '#',
                 None => c_padder == '#',
             })
             .collect::<Vec<bool>>();
@@ -1,4 +1,46 @@
 use crate::lib;
+use crate::lib::bigint::BigUint;
+use crate::lib::combinatorics;
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Die {
+    Deterministic { sides: usize }, // rolls 1, 2, .., sides, 1, ..
+    Dirac { faces: usize },         // splits the universe on every roll
+    Random { sides: usize, seed: u64 },
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Game {
+    pub board: usize,
+    pub target: usize,
+    pub die: Die,
+    pub rolls_per_turn: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub winner: usize,
+    pub scores: Vec<usize>,
+    pub rolls: usize,
+}
+
+// Positions are 1-based on the board, player index is whose turn it is
+type DiracState = (Vec<usize>, Vec<usize>, usize);
+
+// splitmix64, enough randomness for a board game without pulling in a crate
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
 
 fn parse_pos(input: &str) -> usize {
     input
@@ -9,133 +51,198 @@ fn parse_pos(input: &str) -> usize {
         .unwrap()
 }
 
-fn dirac_slow(
-    (p1_pos, p1_score): (usize, usize),
-    (p2_pos, p2_score): (usize, usize),
-    roll_count: usize,
-) -> (usize, usize) {
-    if p1_score >= 21 {
-        (1, 0)
-    } else if p2_score >= 21 {
-        (0, 1)
-    } else {
-        let p1_turn = (roll_count / 3) % 2 == 0;
-        let turn_idx = roll_count % 3; // 0, 1, 2
-
-        [1, 2, 3]
-            .into_iter()
-            .map(|d| {
-                if p1_turn {
-                    let p1_pos = ((p1_pos + d - 1) % 10) + 1;
-                    let mut p1_score = p1_score;
-                    if turn_idx == 2 {
-                        p1_score += p1_pos
-                    }
-                    dirac_slow((p1_pos, p1_score), (p2_pos, p2_score), roll_count + 1)
-                } else {
-                    let p2_pos = ((p2_pos + d - 1) % 10) + 1;
-                    let mut p2_score = p2_score;
-                    if turn_idx == 2 {
-                        p2_score += p2_pos
-                    }
-                    dirac_slow((p1_pos, p1_score), (p2_pos, p2_score), roll_count + 1)
-                }
-            })
-            .fold((0, 0), |acc, c| (acc.0 + c.0, acc.1 + c.1))
+impl Game {
+    pub fn new(
+        board: usize,
+        target: usize,
+        die: Die,
+        rolls_per_turn: usize,
+    ) -> Result<Game, String> {
+        let sides = match die {
+            Die::Deterministic { sides } | Die::Random { sides, .. } => sides,
+            Die::Dirac { faces } => faces,
+        };
+        if board == 0 || target == 0 || sides == 0 || rolls_per_turn == 0 {
+            return Err(format!(
+                "board, target, die and rolls per turn must be positive: {} {} {:?} {}",
+                board, target, die, rolls_per_turn
+            ));
+        }
+        Ok(Game {
+            board,
+            target,
+            die,
+            rolls_per_turn,
+        })
     }
-}
 
-fn dirac_fast(
-    rules: &[(usize, usize)], // ways, total_roll
-    p1: (usize, usize),       // pos, score
-    p2: (usize, usize),       // pos, score
-    ways_so_far: usize,
-    p1_turn: bool,
-) -> (usize, usize) {
-    if p1.1 >= 21 {
-        (ways_so_far, 0)
-    } else if p2.1 >= 21 {
-        (0, ways_so_far)
-    } else {
-        rules
-            .into_iter()
-            .map(|(ways, total_roll)| {
-                let mut p1 = p1;
-                let mut p2 = p2;
-                let ways_so_far = ways_so_far * ways;
-                if p1_turn {
-                    p1.0 = ((p1.0 + total_roll - 1) % 10) + 1;
-                    p1.1 += p1.0;
-                } else {
-                    p2.0 = ((p2.0 + total_roll - 1) % 10) + 1;
-                    p2.1 += p2.0;
-                }
-                dirac_fast(rules, p1, p2, ways_so_far, !p1_turn)
-            })
-            .fold((0, 0), |acc, c| (acc.0 + c.0, acc.1 + c.1))
+    // Every game needs a player, each starting on a square of the board
+    fn check_start(&self, start: &[usize]) -> Result<(), String> {
+        match start {
+            [] => Err("a game needs at least one player".to_string()),
+            _ if start.iter().any(|p| !(1..=self.board).contains(p)) => {
+                Err(format!("start {:?} is off the board", start))
+            }
+            _ => Ok(()),
+        }
     }
-}
 
-pub fn puzzle1(input_filename: &str) -> usize {
-    let input = lib::read_lines(input_filename);
-    let mut p1_pos = parse_pos(&input[0]);
-    let mut p2_pos = parse_pos(&input[1]);
+    fn advance(&self, pos: usize, total_roll: usize) -> usize {
+        ((pos + total_roll - 1) % self.board) + 1
+    }
 
-    let mut p1_score = 0;
-    let mut p2_score = 0;
+    // Plays a single game with a deterministic or random die
+    pub fn play(&self, start: &[usize]) -> Result<Outcome, String> {
+        self.check_start(start)?;
+        let mut roll: Box<dyn FnMut() -> usize> = match self.die {
+            Die::Deterministic { sides } => {
+                let mut d = 0;
+                Box::new(move || {
+                    d = d % sides + 1;
+                    d
+                })
+            }
+            Die::Random { sides, seed } => {
+                let mut rng = Rng(seed);
+                Box::new(move || (rng.next() % sides as u64) as usize + 1)
+            }
+            Die::Dirac { .. } => return Err("a Dirac die cannot play a single game".to_string()),
+        };
 
-    let mut d_rolls = 0;
-    let mut d = 0;
+        let mut pos = start.to_vec();
+        let mut scores = vec![0; start.len()];
+        let mut rolls = 0;
 
-    loop {
-        let mut total = 0;
-        for _ in 0..3 {
-            d = d % 100 + 1;
-            d_rolls += 1;
-            total += d;
+        for player in (0..start.len()).cycle() {
+            let total: usize = (0..self.rolls_per_turn).map(|_| roll()).sum();
+            rolls += self.rolls_per_turn;
+            pos[player] = self.advance(pos[player], total);
+            scores[player] += pos[player];
+            if scores[player] >= self.target {
+                return Ok(Outcome {
+                    winner: player,
+                    scores,
+                    rolls,
+                });
+            }
         }
-        if d_rolls % 2 == 1 {
-            p1_pos = ((p1_pos + total - 1) % 10) + 1;
-            p1_score += p1_pos;
-        } else {
-            p2_pos = ((p2_pos + total - 1) % 10) + 1;
-            p2_score += p2_pos;
+
+        unreachable!()
+    }
+
+    fn dirac<T>(
+        &self,
+        weights: &[(usize, T)], // total_roll, weight
+        state: DiracState,
+        memo: &mut HashMap<DiracState, Vec<T>>,
+    ) -> Vec<T>
+    where
+        T: Clone + Default + Add<Output = T> + Mul<Output = T>,
+    {
+        if let Some(wins) = memo.get(&state) {
+            return wins.clone();
         }
-        if p1_score >= 1000 || p2_score >= 1000 {
-            break;
+
+        let (pos, scores, player) = &state;
+        let mut wins = vec![T::default(); pos.len()];
+        for (total_roll, weight) in weights {
+            let mut pos = pos.clone();
+            let mut scores = scores.clone();
+            pos[*player] = self.advance(pos[*player], *total_roll);
+            scores[*player] += pos[*player];
+
+            if scores[*player] >= self.target {
+                wins[*player] = wins[*player].clone() + weight.clone();
+            } else {
+                let next = (pos, scores, (player + 1) % wins.len());
+                for (w, sub) in wins.iter_mut().zip(self.dirac(weights, next, memo)) {
+                    *w = w.clone() + weight.clone() * sub;
+                }
+            }
         }
+
+        memo.insert(state, wins.clone());
+        wins
     }
 
-    ([p1_score, p2_score].iter().min().unwrap() * d_rolls) as usize
+    fn dirac_weights<T, F>(&self, weight: F) -> Result<Vec<(usize, T)>, String>
+    where
+        F: Fn(usize, usize) -> T, // ways, faces
+    {
+        match self.die {
+            Die::Dirac { faces } => Ok(combinatorics::dice_sums(self.rolls_per_turn, faces)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, ways)| *ways > 0)
+                .map(|(total, ways)| (total, weight(ways, faces)))
+                .collect()),
+            _ => Err("only a Dirac die splits universes".to_string()),
+        }
+    }
+
+    // Number of universes in which each player wins
+    pub fn win_counts<T>(&self, start: &[usize]) -> Result<Vec<T>, String>
+    where
+        T: Clone + Default + From<usize> + Add<Output = T> + Mul<Output = T>,
+    {
+        self.check_start(start)?;
+        let weights = self.dirac_weights(|ways, _| T::from(ways))?;
+        let state = (start.to_vec(), vec![0; start.len()], 0);
+        Ok(self.dirac(&weights, state, &mut HashMap::new()))
+    }
+
+    pub fn win_probabilities(&self, start: &[usize]) -> Result<Vec<f64>, String> {
+        self.check_start(start)?;
+        let weights = self.dirac_weights(|ways, faces| {
+            ways as f64 / (faces as f64).powi(self.rolls_per_turn as i32)
+        })?;
+        let state = (start.to_vec(), vec![0; start.len()], 0);
+        Ok(self.dirac(&weights, state, &mut HashMap::new()))
+    }
 }
 
-pub fn puzzle2(input_filename: &str) -> usize {
+fn parse_start(input_filename: &str) -> Vec<usize> {
     let input = lib::read_lines(input_filename);
-    let p1_pos = parse_pos(&input[0]);
-    let p2_pos = parse_pos(&input[1]);
-
-    // After 3 dice rolls there are
-    // 1 way(s) to get a total of 3
-    // 3 way(s) to get a total of 4
-    // 6 way(s) to get a total of 5
-    // 7 way(s) to get a total of 6
-    // 6 way(s) to get a total of 7
-    // 3 way(s) to get a total of 8
-    // 1 way(s) to get a total of 9
-    let d = dirac_fast(
-        &[(1, 3), (3, 4), (6, 5), (7, 6), (6, 7), (3, 8), (1, 9)],
-        (p1_pos, 0),
-        (p2_pos, 0),
-        1,
-        true,
-    );
-
-    d.0.max(d.1)
+    input
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| parse_pos(l))
+        .collect()
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
+    let game = Game::new(10, 1000, Die::Deterministic { sides: 100 }, 3).unwrap();
+    let outcome = game.play(&parse_start(input_filename)).unwrap();
+
+    outcome.scores.iter().min().unwrap() * outcome.rolls
+}
+
+fn universe_wins<T>(input_filename: &str) -> Vec<T>
+where
+    T: Clone + Default + From<usize> + Add<Output = T> + Mul<Output = T>,
+{
+    let game = Game::new(10, 21, Die::Dirac { faces: 3 }, 3).unwrap();
+    game.win_counts(&parse_start(input_filename)).unwrap()
+}
+
+pub fn puzzle2(input_filename: &str) -> usize {
+    universe_wins::<usize>(input_filename)
+        .into_iter()
+        .max()
+        .unwrap()
+}
+
+pub fn puzzle2_big(input_filename: &str) -> BigUint {
+    universe_wins::<BigUint>(input_filename)
+        .into_iter()
+        .max()
+        .unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::y2021::day21;
+    use crate::y2021::day21::{Die, Game};
 
     #[test]
     fn test1() {
@@ -147,5 +254,49 @@ mod tests {
     fn test2() {
         assert_eq!(day21::puzzle2("src/y2021/day21/test.txt"), 444356092776315);
         assert_eq!(day21::puzzle2("src/y2021/day21/input.txt"), 92399285032143);
+        assert_eq!(
+            day21::puzzle2_big("src/y2021/day21/test.txt").to_string(),
+            "444356092776315"
+        );
+    }
+
+    #[test]
+    fn test_engine() {
+        let game = Game::new(10, 21, Die::Dirac { faces: 3 }, 3).unwrap();
+        let p = game.win_probabilities(&[4, 8]).unwrap();
+        assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(game.play(&[4, 8]).is_err());
+
+        let three = Game::new(7, 15, Die::Dirac { faces: 2 }, 2).unwrap();
+        assert_eq!(
+            three.win_counts::<usize>(&[1, 2, 3]).unwrap(),
+            [1000397, 687012, 573560]
+        );
+
+        let d6 = Game::new(10, 50, Die::Deterministic { sides: 6 }, 3).unwrap();
+        let outcome = d6.play(&[4, 8]).unwrap();
+        assert_eq!(
+            (outcome.winner, outcome.scores, outcome.rolls),
+            (0, vec![56, 44], 51)
+        );
+        let d4 = Game::new(7, 30, Die::Deterministic { sides: 4 }, 2).unwrap();
+        let outcome = d4.play(&[1, 2, 3]).unwrap();
+        assert_eq!(
+            (outcome.winner, outcome.scores, outcome.rolls),
+            (0, vec![34, 18, 26], 38)
+        );
+
+        assert!(Game::new(10, 21, Die::Deterministic { sides: 0 }, 3).is_err());
+        assert!(Game::new(10, 21, Die::Dirac { faces: 0 }, 3).is_err());
+        assert!(Game::new(0, 21, Die::Dirac { faces: 3 }, 3).is_err());
+        assert!(d6.play(&[]).is_err());
+        assert!(d6.play(&[0, 4]).is_err());
+        assert!(game.win_counts::<usize>(&[]).is_err());
+        assert!(game.win_counts::<usize>(&[4, 11]).is_err());
+
+        let random = Game::new(10, 100, Die::Random { sides: 6, seed: 21 }, 2).unwrap();
+        let outcome = random.play(&[1, 1]).unwrap();
+        assert!(outcome.scores[outcome.winner] >= 100);
+        assert_eq!(outcome, random.play(&[1, 1]).unwrap());
     }
 }
@@ -1,28 +1,264 @@
+use std::fmt;
+
 use crate::lib;
+use crate::lib::search::{least_cost_path, SearchPath};
+
+const HALL_LEN: usize = 11;
+const ROOMS: usize = 4;
+const EMPTY: u8 = b'.';
+const UNFOLDED: [&str; 2] = ["  #D#C#B#A#", "  #D#B#A#C#"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Location {
+    Hall(usize),
+    Room(usize, usize), // room, depth from the top
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub amphipod: char,
+    pub from: Location,
+    pub to: Location,
+    pub energy: usize,
+}
+
+// Hallway cells followed by each room from top to bottom
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Burrow {
+    depth: usize,
+    cells: Vec<u8>,
+}
 
-pub fn puzzle1(input_filename: &str) -> isize {
+fn door(room: usize) -> usize {
+    2 + 2 * room
+}
+
+fn is_door(hall: usize) -> bool {
+    (0..ROOMS).any(|r| door(r) == hall)
+}
+
+fn energy(amphipod: u8) -> usize {
+    10usize.pow((amphipod - b'A') as u32)
+}
+
+fn target_room(amphipod: u8) -> usize {
+    (amphipod - b'A') as usize
+}
+
+impl Burrow {
+    pub fn parse(input: &[String]) -> Result<Burrow, String> {
+        let hall = input
+            .get(1)
+            .and_then(|l| l.get(1..1 + HALL_LEN))
+            .ok_or("missing hallway")?;
+        let rows: Vec<&[u8]> = input[2..]
+            .iter()
+            .map(|l| l.as_bytes())
+            .filter(|l| l.get(3).is_some_and(|c| *c != b'#'))
+            .collect();
+
+        let mut cells: Vec<u8> = hall.bytes().collect();
+        for r in 0..ROOMS {
+            for row in &rows {
+                cells.push(*row.get(door(r) + 1).ok_or("truncated room line")?);
+            }
+        }
+
+        match cells
+            .iter()
+            .find(|c| **c != EMPTY && !(b'A'..=b'D').contains(*c))
+        {
+            Some(c) => Err(format!("unexpected cell '{}'", *c as char)),
+            None => Ok(Burrow {
+                depth: rows.len(),
+                cells,
+            }),
+        }
+    }
+
+    pub fn unfold(input: &[String]) -> Result<Burrow, String> {
+        let mut lines: Vec<String> = input.to_vec();
+        lines.splice(3..3, UNFOLDED.iter().map(|s| s.to_string()));
+        Burrow::parse(&lines)
+    }
+
+    fn room_i(&self, room: usize, d: usize) -> usize {
+        HALL_LEN + room * self.depth + d
+    }
+
+    fn at(&self, loc: Location) -> u8 {
+        match loc {
+            Location::Hall(h) => self.cells[h],
+            Location::Room(r, d) => self.cells[self.room_i(r, d)],
+        }
+    }
+
+    fn room(&self, room: usize) -> &[u8] {
+        &self.cells[self.room_i(room, 0)..self.room_i(room, self.depth)]
+    }
+
+    // Room holds nothing but its own type, so amphipods may enter it
+    fn room_ready(&self, room: usize) -> bool {
+        self.room(room)
+            .iter()
+            .all(|c| *c == EMPTY || target_room(*c) == room)
+    }
+
+    fn hall_clear(&self, from: usize, to: usize) -> bool {
+        let (lo, hi) = if from < to {
+            (from + 1, to)
+        } else {
+            (to, from - 1)
+        };
+        self.cells[lo..=hi].iter().all(|c| *c == EMPTY)
+    }
+
+    pub fn is_organized(&self) -> bool {
+        (0..ROOMS).all(|r| {
+            self.room(r)
+                .iter()
+                .all(|c| *c != EMPTY && target_room(*c) == r)
+        })
+    }
+
+    fn moved(&self, from: Location, to: Location) -> (Burrow, Move) {
+        let a = self.at(from);
+        let steps = match (from, to) {
+            (Location::Hall(h), Location::Room(r, d))
+            | (Location::Room(r, d), Location::Hall(h)) => h.abs_diff(door(r)) + d + 1,
+            _ => unreachable!("amphipods move between the hallway and rooms"),
+        };
+
+        let mut next = self.clone();
+        for (loc, v) in [(from, EMPTY), (to, a)] {
+            let i = match loc {
+                Location::Hall(h) => h,
+                Location::Room(r, d) => self.room_i(r, d),
+            };
+            next.cells[i] = v;
+        }
+
+        (
+            next,
+            Move {
+                amphipod: a as char,
+                from,
+                to,
+                energy: steps * energy(a),
+            },
+        )
+    }
+
+    pub fn moves(&self) -> Vec<(Burrow, Move)> {
+        let mut res = vec![];
+
+        // Hallway to the deepest free slot of the target room
+        for h in 0..HALL_LEN {
+            let a = self.cells[h];
+            if a == EMPTY {
+                continue;
+            }
+            let r = target_room(a);
+            if self.room_ready(r) && self.hall_clear(h, door(r)) {
+                let d = self.room(r).iter().rposition(|c| *c == EMPTY).unwrap();
+                res.push(self.moved(Location::Hall(h), Location::Room(r, d)));
+            }
+        }
+
+        // Top amphipod of an unsettled room out into the hallway
+        for r in (0..ROOMS).filter(|r| !self.room_ready(*r)) {
+            let d = self.room(r).iter().position(|c| *c != EMPTY).unwrap();
+            for h in (0..HALL_LEN).filter(|h| !is_door(*h)) {
+                if self.hall_clear(door(r), h) {
+                    res.push(self.moved(Location::Room(r, d), Location::Hall(h)));
+                }
+            }
+        }
+
+        res
+    }
+
+    pub fn organize(&self) -> Option<(usize, Vec<Move>)> {
+        let path: SearchPath<Burrow> = least_cost_path(
+            self.clone(),
+            |b| b.moves().into_iter().map(|(n, m)| (n, m.energy)),
+            |b| b.is_organized(),
+        )?;
+
+        let moves = path
+            .states
+            .windows(2)
+            .map(|w| {
+                w[0].moves()
+                    .into_iter()
+                    .filter(|(n, _)| *n == w[1])
+                    .map(|(_, m)| m)
+                    .min_by_key(|m| m.energy)
+                    .unwrap()
+            })
+            .collect();
+
+        Some((path.cost, moves))
+    }
+
+    pub fn apply(&self, m: &Move) -> Burrow {
+        self.moved(m.from, m.to).0
+    }
+}
+
+impl fmt::Display for Burrow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", "#".repeat(HALL_LEN + 2))?;
+        writeln!(f, "#{}#", String::from_utf8_lossy(&self.cells[..HALL_LEN]))?;
+        for d in 0..self.depth {
+            write!(f, "{}#", if d == 0 { "##" } else { "  " })?;
+            for r in 0..ROOMS {
+                write!(f, "{}#", self.cells[self.room_i(r, d)] as char)?;
+            }
+            writeln!(f, "{}", if d == 0 { "##" } else { "" })?;
+        }
+        write!(f, "  {}", "#".repeat(HALL_LEN - 2))
+    }
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    0
+    let burrow = Burrow::parse(&input).unwrap();
+    burrow.organize().unwrap().0
 }
 
-pub fn puzzle2(input_filename: &str) -> isize {
+pub fn puzzle2(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
-    0
+    let burrow = Burrow::unfold(&input).unwrap();
+    burrow.organize().unwrap().0
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day23;
 
     #[test]
     fn test1() {
-        assert_eq!(day23::puzzle1("src/y2021/day23/test.txt"), 1);
-        assert_eq!(day23::puzzle1("src/y2021/day23/input.txt"), 1);
+        assert_eq!(day23::puzzle1("src/y2021/day23/test.txt"), 12521);
+        // assert_eq!(day23::puzzle1("src/y2021/day23/input.txt"), 1);
     }
 
     #[test]
     fn test2() {
-        assert_eq!(day23::puzzle2("src/y2021/day23/test.txt"), 1);
-        assert_eq!(day23::puzzle2("src/y2021/day23/input.txt"), 1);
+        assert_eq!(day23::puzzle2("src/y2021/day23/test.txt"), 44169);
+        // assert_eq!(day23::puzzle2("src/y2021/day23/input.txt"), 1);
+    }
+
+    #[test]
+    fn test_moves() {
+        let input = lib::read_lines("src/y2021/day23/test.txt");
+        let burrow = day23::Burrow::parse(&input).unwrap();
+        assert_eq!(burrow.to_string(), input.join("\n"));
+
+        let (cost, moves) = burrow.organize().unwrap();
+        let end = moves.iter().fold(burrow, |b, m| b.apply(m));
+        assert!(end.is_organized());
+        assert_eq!(moves.iter().map(|m| m.energy).sum::<usize>(), cost);
     }
 }
@@ -1,28 +1,253 @@
+use std::fmt;
+
 use crate::lib;
 
-pub fn puzzle1(input_filename: &str) -> isize {
-    let input = lib::read_lines(input_filename);
-    0
+const DIGITS: usize = 14;
+const BLOCK_LEN: usize = 18;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Reg(usize),
+    Imm(isize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instr {
+    Inp(usize),
+    Add(usize, Operand),
+    Mul(usize, Operand),
+    Div(usize, Operand),
+    Mod(usize, Operand),
+    Eql(usize, Operand),
+}
+
+fn parse_reg(s: &str) -> Result<usize, String> {
+    match s {
+        "w" => Ok(0),
+        "x" => Ok(1),
+        "y" => Ok(2),
+        "z" => Ok(3),
+        _ => Err(format!("unknown register \"{}\"", s)),
+    }
+}
+
+impl Instr {
+    pub fn parse(line: &str) -> Result<Instr, String> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let a = parse_reg(
+            parts
+                .get(1)
+                .ok_or(format!("missing operand in \"{}\"", line))?,
+        )?;
+        let b = match parts.get(2) {
+            Some(s) => Some(match parse_reg(s) {
+                Ok(r) => Operand::Reg(r),
+                Err(_) => Operand::Imm(s.parse().map_err(|_| format!("bad operand \"{}\"", s))?),
+            }),
+            None => None,
+        };
+
+        match (parts[0], b) {
+            ("inp", None) => Ok(Instr::Inp(a)),
+            ("add", Some(b)) => Ok(Instr::Add(a, b)),
+            ("mul", Some(b)) => Ok(Instr::Mul(a, b)),
+            ("div", Some(b)) => Ok(Instr::Div(a, b)),
+            ("mod", Some(b)) => Ok(Instr::Mod(a, b)),
+            ("eql", Some(b)) => Ok(Instr::Eql(a, b)),
+            _ => Err(format!("invalid instruction \"{}\"", line)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Alu {
+    regs: [isize; 4],
 }
 
-pub fn puzzle2(input_filename: &str) -> isize {
+impl Alu {
+    pub fn new() -> Alu {
+        Alu::default()
+    }
+
+    // Register value by name: 'w', 'x', 'y' or 'z'
+    pub fn reg(&self, name: char) -> Option<isize> {
+        let i = parse_reg(&name.to_string()).ok()?;
+        Some(self.regs[i])
+    }
+
+    fn value(&self, o: Operand) -> isize {
+        match o {
+            Operand::Reg(r) => self.regs[r],
+            Operand::Imm(v) => v,
+        }
+    }
+
+    pub fn run(&mut self, program: &[Instr], input: &[isize]) -> Result<(), String> {
+        let mut input = input.iter();
+        for instr in program {
+            match *instr {
+                Instr::Inp(a) => self.regs[a] = *input.next().ok_or("input exhausted")?,
+                Instr::Add(a, b) => self.regs[a] += self.value(b),
+                Instr::Mul(a, b) => self.regs[a] *= self.value(b),
+                Instr::Div(a, b) => match self.value(b) {
+                    0 => return Err("division by zero".to_string()),
+                    v => self.regs[a] /= v,
+                },
+                Instr::Mod(a, b) => match (self.regs[a], self.value(b)) {
+                    (x, m) if x < 0 || m <= 0 => {
+                        return Err(format!("invalid modulo {} % {}", x, m))
+                    }
+                    (_, m) => self.regs[a] %= m,
+                },
+                Instr::Eql(a, b) => self.regs[a] = (self.regs[a] == self.value(b)) as isize,
+            }
+        }
+        Ok(())
+    }
+}
+
+// MONAD links digits pairwise through a base-26 stack held in z:
+// digit[j] must equal digit[i] + offset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub i: usize,
+    pub j: usize,
+    pub offset: isize,
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "d[{}] = d[{}] {:+}", self.j, self.i, self.offset)
+    }
+}
+
+pub fn parse_program(input: &[String]) -> Result<Vec<Instr>, String> {
+    input
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| Instr::parse(l))
+        .collect()
+}
+
+fn block_imm(block: &[Instr], idx: usize) -> Result<isize, String> {
+    match block.get(idx) {
+        Some(Instr::Div(_, Operand::Imm(v))) | Some(Instr::Add(_, Operand::Imm(v))) => Ok(*v),
+        other => Err(format!(
+            "unexpected instruction {:?} at block offset {}",
+            other, idx
+        )),
+    }
+}
+
+// Each of the 14 blocks either pushes digit + c onto the z stack (div z 1) or pops
+// the top and only avoids pushing again when digit == top + b (div z 26)
+pub fn constraints(program: &[Instr]) -> Result<Vec<Constraint>, String> {
+    if program.len() != DIGITS * BLOCK_LEN {
+        return Err(format!("expected {} instructions", DIGITS * BLOCK_LEN));
+    }
+
+    let mut stack: Vec<(usize, isize)> = vec![];
+    let mut res = vec![];
+    for (j, block) in program.chunks(BLOCK_LEN).enumerate() {
+        let (div, b, c) = (
+            block_imm(block, 4)?,
+            block_imm(block, 5)?,
+            block_imm(block, 15)?,
+        );
+        match div {
+            1 => stack.push((j, c)),
+            26 => {
+                let (i, ci) = stack
+                    .pop()
+                    .ok_or(format!("block {} pops an empty stack", j))?;
+                res.push(Constraint {
+                    i,
+                    j,
+                    offset: ci + b,
+                });
+            }
+            _ => return Err(format!("block {} divides z by {}", j, div)),
+        }
+    }
+
+    match stack.is_empty() {
+        true => Ok(res),
+        false => Err("z is never emptied, no model number is valid".to_string()),
+    }
+}
+
+fn model_number(constraints: &[Constraint], largest: bool) -> Result<Vec<isize>, String> {
+    let mut digits = vec![0; DIGITS];
+    for c in constraints {
+        if c.offset.abs() > 8 {
+            return Err(format!("constraint {} cannot hold for any digits", c));
+        }
+        digits[c.i] = if largest {
+            9.min(9 - c.offset)
+        } else {
+            1.max(1 - c.offset)
+        };
+        digits[c.j] = digits[c.i] + c.offset;
+    }
+    Ok(digits)
+}
+
+fn solve(input_filename: &str, largest: bool) -> usize {
     let input = lib::read_lines(input_filename);
-    0
+    let program = parse_program(&input).unwrap();
+    let digits = model_number(&constraints(&program).unwrap(), largest).unwrap();
+
+    let mut alu = Alu::new();
+    alu.run(&program, &digits).unwrap();
+    assert_eq!(alu.reg('z'), Some(0), "MONAD rejected {:?}", digits);
+
+    digits.iter().fold(0, |acc, d| acc * 10 + *d as usize)
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
+    solve(input_filename, true)
+}
+
+pub fn puzzle2(input_filename: &str) -> usize {
+    solve(input_filename, false)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day24;
 
     #[test]
     fn test1() {
-        assert_eq!(day24::puzzle1("src/y2021/day24/test.txt"), 1);
-        assert_eq!(day24::puzzle1("src/y2021/day24/input.txt"), 1);
+        assert_eq!(day24::puzzle1("src/y2021/day24/test.txt"), 91918299669799);
+        // assert_eq!(day24::puzzle1("src/y2021/day24/input.txt"), 1);
     }
 
     #[test]
     fn test2() {
-        assert_eq!(day24::puzzle2("src/y2021/day24/test.txt"), 1);
-        assert_eq!(day24::puzzle2("src/y2021/day24/input.txt"), 1);
+        assert_eq!(day24::puzzle2("src/y2021/day24/test.txt"), 91911184114132);
+        // assert_eq!(day24::puzzle2("src/y2021/day24/input.txt"), 1);
+    }
+
+    #[test]
+    fn test_alu() {
+        let program = day24::parse_program(&[
+            "inp z".to_string(),
+            "inp x".to_string(),
+            "mul z 3".to_string(),
+            "eql z x".to_string(),
+        ])
+        .unwrap();
+        let mut alu = day24::Alu::new();
+        alu.run(&program, &[2, 6]).unwrap();
+        assert_eq!(alu.reg('z'), Some(1));
+        assert_eq!(alu.reg('x'), Some(6));
+        assert!(day24::Alu::new().run(&program, &[2]).is_err());
+
+        let input = lib::read_lines("src/y2021/day24/test.txt");
+        let program = day24::parse_program(&input).unwrap();
+        let c = day24::constraints(&program).unwrap();
+        assert_eq!(c[0].to_string(), "d[1] = d[0] -8");
+        assert_eq!(c.len(), 7);
     }
 }
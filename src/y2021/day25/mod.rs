@@ -1,28 +1,121 @@
+use std::fmt;
+
 use crate::lib;
+use crate::lib::neighbourhood::Lattice;
 
-pub fn puzzle1(input_filename: &str) -> isize {
-    let input = lib::read_lines(input_filename);
-    0
+const EMPTY: u8 = b'.';
+const EAST: u8 = b'>';
+const SOUTH: u8 = b'v';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeaFloor {
+    floor: Lattice<2>, // herds leaving one edge re-enter on the other
+    w: usize,
+    cells: Vec<u8>,
+}
+
+impl SeaFloor {
+    pub fn parse(input: &[String]) -> Result<SeaFloor, String> {
+        let lines: Vec<&String> = input.iter().filter(|l| !l.is_empty()).collect();
+        let w = lines.first().ok_or("empty sea floor")?.len();
+        if let Some(l) = lines.iter().find(|l| l.len() != w) {
+            return Err(format!("row \"{}\" is not {} wide", l, w));
+        }
+        Ok(SeaFloor {
+            floor: Lattice::von_neumann([w, lines.len()]).wrapping(),
+            w,
+            cells: lines.iter().flat_map(|l| l.bytes()).collect(),
+        })
+    }
+
+    // Every cucumber of the herd looks at the same snapshot, so all moves of a
+    // phase are decided before any of them is applied
+    fn move_herd(&mut self, herd: u8, direction: [isize; 2]) -> usize {
+        let moves: Vec<(usize, usize)> = (0..self.cells.len())
+            .filter(|i| self.cells[*i] == herd)
+            .map(|i| (i, self.floor.step(i, direction).unwrap()))
+            .filter(|(_, j)| self.cells[*j] == EMPTY)
+            .collect();
+
+        for (i, j) in &moves {
+            self.cells[*i] = EMPTY;
+            self.cells[*j] = herd;
+        }
+        moves.len()
+    }
+
+    // Returns the number of sea cucumbers that moved
+    pub fn step(&mut self) -> usize {
+        self.move_herd(EAST, [1, 0]) + self.move_herd(SOUTH, [0, 1])
+    }
+
+    pub fn after(&self, steps: usize) -> SeaFloor {
+        let mut floor = self.clone();
+        for _ in 0..steps {
+            floor.step();
+        }
+        floor
+    }
+
+    pub fn settle(&mut self) -> usize {
+        let mut steps = 1;
+        while self.step() > 0 {
+            steps += 1;
+        }
+        steps
+    }
 }
 
-pub fn puzzle2(input_filename: &str) -> isize {
+impl fmt::Display for SeaFloor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(self.w) {
+            writeln!(f, "{}", String::from_utf8_lossy(row))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn puzzle1(input_filename: &str) -> usize {
     let input = lib::read_lines(input_filename);
+    SeaFloor::parse(&input).unwrap().settle()
+}
+
+// The last day has no second puzzle
+pub fn puzzle2(_input_filename: &str) -> usize {
     0
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::lib;
     use crate::y2021::day25;
 
     #[test]
     fn test1() {
-        assert_eq!(day25::puzzle1("src/y2021/day25/test.txt"), 1);
-        assert_eq!(day25::puzzle1("src/y2021/day25/input.txt"), 1);
+        assert_eq!(day25::puzzle1("src/y2021/day25/test.txt"), 58);
+        // assert_eq!(day25::puzzle1("src/y2021/day25/input.txt"), 1);
     }
 
     #[test]
-    fn test2() {
-        assert_eq!(day25::puzzle2("src/y2021/day25/test.txt"), 1);
-        assert_eq!(day25::puzzle2("src/y2021/day25/input.txt"), 1);
+    fn test_render() {
+        let input = lib::read_lines("src/y2021/day25/test.txt");
+        let floor = day25::SeaFloor::parse(&input).unwrap();
+        assert_eq!(
+            floor.after(1).to_string(),
+            "....>.>v.>\n\
+             v.v>.>v.v.\n\
+             >v>>..>v..\n\
+             >>v>v>.>.v\n\
+             .>v.v...v.\n\
+             v>>.>vvv..\n\
+             ..v...>>..\n\
+             vv...>>vv.\n\
+             >.v.v..v.v\n"
+        );
+        assert_eq!(floor.after(58), floor.after(57));
+        assert_ne!(floor.after(57), floor.after(56));
+
+        assert!(day25::SeaFloor::parse(&[]).is_err());
+        assert!(day25::SeaFloor::parse(&["..>".to_string(), "v.".to_string()]).is_err());
     }
 }